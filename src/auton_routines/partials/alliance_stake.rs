@@ -1,5 +1,7 @@
 use alloc::rc::Rc;
 
+use lamlib_rs::differential::motions::trigger::TriggerCondition;
+
 use crate::Robot;
 
 /// A function to drive away a specific distance and use the Ladybrown to score.
@@ -8,13 +10,13 @@ pub async fn run_alliance_stake(robot: &mut Robot) {
     let chassis = robot.chassis.clone();
     robot.intake.lock().await.set_velocity(-0.1);
 
-    Rc::clone(&chassis)
+    let motion = Rc::clone(&chassis)
         .move_relative()
         .distance(10.0)
         .call()
         .await;
 
-    chassis.wait_until(5.0).await;
+    motion.when(TriggerCondition::Distance(5.0)).await;
     robot
         .ladybrown_arm
         .borrow_mut()
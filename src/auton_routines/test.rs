@@ -4,7 +4,8 @@ use core::time::Duration;
 
 use async_trait::async_trait;
 use lamlib_rs::{
-    params_ramsete_h, params_turn_to, unsigned_mod,
+    controllers::autotune::TuningRule,
+    params_ramsete_h, params_relay_autotune, params_turn_to, unsigned_mod,
     utils::{math::AngleExt, timer::Timer, AllianceColor, TILE_SIZE},
 };
 use vexide::io::{println, Write};
@@ -20,6 +21,10 @@ pub enum TestMode {
     TrackingCenter(Duration, f64),
     ImuScalar,
     ParticleFilter,
+
+    /// Relay autotunes with the given relay amplitude.
+    AngularAutotune(f64),
+    LinearAutotune(f64),
     Default,
 }
 
@@ -101,6 +106,36 @@ impl AutonRoutine for Test {
                 chassis.arcade(0.0, 0.0, false);
             }
             TestMode::ImuScalar => {}
+            TestMode::AngularAutotune(relay_amplitude)
+            | TestMode::LinearAutotune(relay_amplitude) => {
+                let params = params_relay_autotune!(relay_amplitude: relay_amplitude);
+                let result = if matches!(TEST_MODE, TestMode::AngularAutotune(_)) {
+                    chassis
+                        .clone()
+                        .autotune_angular()
+                        .params(params)
+                        .call()
+                        .await
+                } else {
+                    chassis
+                        .clone()
+                        .autotune_linear()
+                        .params(params)
+                        .call()
+                        .await
+                };
+                if let Some(result) = result {
+                    for rule in [
+                        TuningRule::ZieglerNichols,
+                        TuningRule::TyreusLuyben,
+                        TuningRule::NoOvershoot,
+                    ] {
+                        println!("{:?}: {:?}", rule, result.gains(rule));
+                    }
+                } else {
+                    println!("Autotune did not settle into oscillation.");
+                }
+            }
             TestMode::ParticleFilter => {
                 if let Some(filter_state) = chassis.filter_state().await {
                     chassis.set_filter_state(!filter_state).await;
@@ -0,0 +1,254 @@
+use alloc::{rc::Rc, vec::Vec};
+use core::{cell::RefCell, f64::consts::PI, time::Duration};
+
+use bon::Builder;
+use log::info;
+use vexide::{
+    float::Float,
+    prelude::{BrakeMode, Motor, MotorControl},
+};
+
+use super::pid::PIDGains;
+use crate::{
+    devices::motor_group::MotorGroup,
+    utils::{
+        clock::{vex_clock, Clock},
+        timer::Timer,
+    },
+};
+
+/// Rules for turning an ultimate gain and period into PID gains.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TuningRule {
+    /// Classic Ziegler–Nichols. Fast, with noticeable overshoot.
+    ZieglerNichols,
+
+    /// Tyreus–Luyben. More conservative than Ziegler–Nichols, with less oscillation.
+    TyreusLuyben,
+
+    /// Ziegler–Nichols "no overshoot" variant.
+    NoOvershoot,
+}
+
+impl TuningRule {
+    /// The ratios (Kp / Ku, Ti / Pu, Td / Pu) of the rule.
+    fn ratios(&self) -> (f64, f64, f64) {
+        match self {
+            TuningRule::ZieglerNichols => (0.6, 0.5, 0.125),
+            TuningRule::TyreusLuyben => (1.0 / 2.2, 2.2, 1.0 / 6.3),
+            TuningRule::NoOvershoot => (0.2, 0.5, 1.0 / 3.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RelayAutotuneResult {
+    /// The ultimate gain, Ku.
+    pub ultimate_gain: f64,
+
+    /// The ultimate period, Pu.
+    pub ultimate_period: Duration,
+
+    /// The averaged amplitude of the process variable's oscillation.
+    pub amplitude: f64,
+}
+
+impl RelayAutotuneResult {
+    /// Suggested gains for [`crate::controllers::pid::PID`] under a [`TuningRule`].
+    ///
+    /// The PID integrates and differentiates with respect to milliseconds,
+    /// so the integral and derivative times are in milliseconds as well.
+    pub fn gains(&self, rule: TuningRule) -> PIDGains<f64> {
+        let (kp_ratio, ti_ratio, td_ratio) = rule.ratios();
+        let period = self.ultimate_period.as_secs_f64() * 1000.0;
+        let kp = kp_ratio * self.ultimate_gain;
+        PIDGains::new(kp, kp / (ti_ratio * period), kp * td_ratio * period)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Builder)]
+pub struct RelayAutotuneParameters {
+    /// The relay output is `bias ± relay_amplitude`.
+    pub relay_amplitude: f64,
+
+    /// Output added to the relay, such as to hold an arm against gravity.
+    #[builder(default = 0.0)]
+    pub bias: f64,
+
+    /// Error band in which the relay does not switch, to reject sensor noise.
+    #[builder(default = 0.0)]
+    pub hysteresis: f64,
+
+    /// The number of oscillations averaged, at least one. The first oscillation is
+    /// always discarded.
+    #[builder(default = 4)]
+    pub cycles: usize,
+}
+
+#[macro_export]
+macro_rules! params_relay_autotune {
+    (
+        $($key:ident : $value:expr),* $(,)?
+    ) => {
+        $crate::controllers::autotune::RelayAutotuneParameters::builder()
+            $(.$key($value))*
+            .build()
+    };
+}
+pub use params_relay_autotune;
+
+/// Åström–Hägglund relay feedback.
+///
+/// The process is driven with a bang-bang relay around the set point until it
+/// oscillates steadily. From the amplitude `a` of the oscillation and the relay
+/// amplitude `d`, the ultimate gain is `4d / (π √(a² - ε²))`, where `ε` is the
+/// hysteresis. The ultimate period is the period of the oscillation.
+pub struct RelayAutotuner {
+    set_point: f64,
+    params: RelayAutotuneParameters,
+    relay_high: bool,
+
+    /// Start of the current oscillation, at the relay switching high.
+    cycle_start: Option<Duration>,
+    cycle_max: f64,
+    cycle_min: f64,
+
+    /// Pairs of the amplitude and period of each full oscillation.
+    oscillations: Vec<(f64, Duration)>,
+    clock: Rc<dyn Clock>,
+}
+
+impl RelayAutotuner {
+    /// # Panics
+    ///
+    /// Panics if the relay amplitude is not positive or if no cycles are averaged.
+    pub fn new(set_point: f64, params: RelayAutotuneParameters) -> Self {
+        assert!(
+            params.relay_amplitude > 0.0,
+            "Relay amplitude must be positive."
+        );
+        assert!(params.cycles >= 1, "At least one cycle must be averaged.");
+        Self {
+            set_point,
+            params,
+            relay_high: true,
+            cycle_start: None,
+            cycle_max: f64::NEG_INFINITY,
+            cycle_min: f64::INFINITY,
+            oscillations: Vec::new(),
+            clock: vex_clock(),
+        }
+    }
+
+    /// Times the oscillations with `clock`, such as a
+    /// [`crate::utils::clock::MockClock`].
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Updates the relay with a new measurement and returns the output to apply.
+    pub fn update(&mut self, process_variable: f64) -> f64 {
+        let error = self.set_point - process_variable;
+        self.cycle_max = self.cycle_max.max(process_variable);
+        self.cycle_min = self.cycle_min.min(process_variable);
+
+        if error > self.params.hysteresis && !self.relay_high {
+            self.relay_high = true;
+            let now = self.clock.now();
+            if let Some(cycle_start) = self.cycle_start {
+                self.oscillations.push((
+                    (self.cycle_max - self.cycle_min) / 2.0,
+                    now.saturating_sub(cycle_start),
+                ));
+            }
+            self.cycle_start = Some(now);
+            self.cycle_max = process_variable;
+            self.cycle_min = process_variable;
+        } else if error < -self.params.hysteresis && self.relay_high {
+            self.relay_high = false;
+        }
+
+        if self.is_done() {
+            return self.params.bias;
+        }
+        if self.relay_high {
+            self.params.bias + self.params.relay_amplitude
+        } else {
+            self.params.bias - self.params.relay_amplitude
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.oscillations.len() > self.params.cycles
+    }
+
+    /// The measured result, once enough oscillations have been observed.
+    pub fn result(&self) -> Option<RelayAutotuneResult> {
+        if !self.is_done() {
+            return None;
+        }
+        // The first oscillation starts from rest, so it is skipped.
+        let measured = &self.oscillations[1..];
+        let amplitude = measured.iter().map(|(a, _)| a).sum::<f64>() / measured.len() as f64;
+        let period = measured.iter().map(|(_, p)| *p).sum::<Duration>() / measured.len() as u32;
+        let effective_amplitude =
+            (amplitude.powi(2) - self.params.hysteresis.powi(2)).max(f64::EPSILON);
+        Some(RelayAutotuneResult {
+            ultimate_gain: 4.0 * self.params.relay_amplitude / (PI * effective_amplitude.sqrt()),
+            ultimate_period: period,
+            amplitude,
+        })
+    }
+}
+
+/// Runs a relay autotune on a motor group, such as an arm, until it settles into
+/// steady oscillation or the timeout elapses.
+///
+/// The relay output is a voltage, and `position` is read every motor update.
+/// The motors hold their position afterwards.
+///
+/// # Example
+///
+/// ```
+/// let result = relay_autotune_motor_group(
+///     motor_group.clone(),
+///     || Some(rotation_sensor.borrow().position().ok()?.as_degrees()),
+///     90.0,
+///     params_relay_autotune!(relay_amplitude: 4.0, bias: 1.0),
+///     Duration::from_secs(10),
+/// )
+/// .await;
+/// ```
+pub async fn relay_autotune_motor_group(
+    motor_group: Rc<RefCell<MotorGroup>>,
+    mut position: impl FnMut() -> Option<f64>,
+    set_point: f64,
+    params: RelayAutotuneParameters,
+    timeout: Duration,
+) -> Option<RelayAutotuneResult> {
+    let mut autotuner = RelayAutotuner::new(set_point, params);
+    let mut timer = Timer::new(timeout);
+    while !timer.is_done() && !autotuner.is_done() {
+        if let Some(position) = position() {
+            let output = autotuner.update(position);
+            motor_group.borrow_mut().set_voltage_all_for_types(
+                output,
+                output * Motor::EXP_MAX_VOLTAGE / Motor::V5_MAX_VOLTAGE,
+            );
+        }
+        vexide::time::sleep(Motor::UPDATE_INTERVAL).await;
+    }
+    motor_group
+        .borrow_mut()
+        .set_target_all(MotorControl::Brake(BrakeMode::Hold));
+
+    let result = autotuner.result();
+    if let Some(result) = result {
+        info!(
+            "Relay autotune: Ku: {}, Pu: {:?}",
+            result.ultimate_gain, result.ultimate_period
+        );
+    }
+    result
+}
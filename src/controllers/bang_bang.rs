@@ -0,0 +1,64 @@
+use num_traits::Float;
+
+use super::{ControllerTelemetry, FeedbackController};
+
+/// A bang-bang controller that switches between two outputs.
+///
+/// The output switches high once the error exceeds the hysteresis band and low once
+/// it falls below the negative of it. Inside the band, the previous output is held.
+#[derive(Clone)]
+pub struct BangBang<T: Float> {
+    high_output: T,
+    low_output: T,
+
+    /// Half-width of the band around the set point where the output does not switch.
+    hysteresis: T,
+
+    is_high: bool,
+    telemetry: Option<ControllerTelemetry<T>>,
+}
+
+impl<T: Float> BangBang<T> {
+    pub fn new(high_output: T, low_output: T, hysteresis: Option<T>) -> Self {
+        Self {
+            high_output,
+            low_output,
+            hysteresis: hysteresis.unwrap_or(T::zero()).abs(),
+            is_high: false,
+            telemetry: None,
+        }
+    }
+}
+
+impl<T: Float> FeedbackController<T> for BangBang<T> {
+    fn update(&mut self, set_point: T, process_variable: T) -> T {
+        let error = set_point - process_variable;
+        if error > self.hysteresis {
+            self.is_high = true;
+        } else if error < -self.hysteresis {
+            self.is_high = false;
+        }
+
+        let output = if self.is_high {
+            self.high_output
+        } else {
+            self.low_output
+        };
+        // Outside the hysteresis band, the output is at the rail that the error
+        // pushes towards. Inside it, the output is only being held.
+        self.telemetry = Some(ControllerTelemetry {
+            saturated: error.abs() > self.hysteresis,
+            ..ControllerTelemetry::from_output(error, output)
+        });
+        output
+    }
+
+    fn reset(&mut self) {
+        self.is_high = false;
+        self.telemetry = None;
+    }
+
+    fn telemetry(&self) -> Option<ControllerTelemetry<T>> {
+        self.telemetry
+    }
+}
@@ -0,0 +1,135 @@
+use alloc::{boxed::Box, rc::Rc, vec::Vec};
+use core::cell::RefCell;
+
+use num_traits::Float;
+
+use super::{ControllerTelemetry, FeedbackController, FeedforwardController};
+use crate::{
+    avg_valid, devices::motor_group::MotorGroup, utils::differential_tracker::DifferentialTracker,
+};
+
+/// Where the inner loop of a [`CascadeController`] reads velocity from.
+#[derive(Clone)]
+pub enum VelocitySource<T> {
+    Closure(Rc<dyn Fn() -> Option<T>>),
+
+    /// The average velocity of a motor group in RPM, multiplied by a scalar,
+    /// such as to convert it to the units of the position loop per second.
+    MotorGroup(Rc<RefCell<MotorGroup>>, T),
+}
+
+impl<T: Float> VelocitySource<T> {
+    pub fn velocity(&self) -> Option<T> {
+        match self {
+            VelocitySource::Closure(velocity) => velocity(),
+            VelocitySource::MotorGroup(motor_group, scalar) => {
+                let velocities = motor_group
+                    .borrow()
+                    .velocity_all()
+                    .into_iter()
+                    .map(|velocity| velocity.ok())
+                    .collect::<Vec<_>>();
+                avg_valid!(velocities)
+                    .and_then(T::from)
+                    .map(|velocity| velocity * *scalar)
+            }
+        }
+    }
+}
+
+/// A position loop cascaded into a velocity loop.
+///
+/// The outer controller turns position error into a velocity set point, which
+/// the inner controller tracks using the [`VelocitySource`]. If a feedforward is
+/// given, it is fed the velocity set point and its output is added to that of the
+/// inner loop. If the velocity cannot be read, it is taken to equal the set point,
+/// so the inner loop sees no new error and only its integral and the feedforward
+/// contribute.
+///
+/// # Example
+///
+/// ```
+/// let arm_controller = CascadeController::new(
+///     Box::new(PID::new(4.0, 0.0, 0.0, 0.0, false, 2)),
+///     Box::new(PID::new(0.05, 0.0, 0.0, 0.0, false, 2)),
+///     VelocitySource::MotorGroup(arm_motors.clone(), 6.0), // RPM to degrees per second.
+///     Some(Box::new(SimpleMotorFeedforward::new(0.3, 0.01, 0.0, None))),
+///     Some(300.0),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct CascadeController<T: Float> {
+    /// The position loop, whose output is the velocity set point.
+    outer: Box<dyn FeedbackController<T>>,
+
+    /// The velocity loop.
+    inner: Box<dyn FeedbackController<T>>,
+    velocity_source: VelocitySource<T>,
+    feedforward: Option<Box<dyn FeedforwardController<T>>>,
+
+    /// Tracks the velocity set point for the feedforward.
+    velocity_set_point_tracker: DifferentialTracker<T>,
+
+    /// Limits the magnitude of the velocity set point.
+    max_velocity: Option<T>,
+    feedforward_output: T,
+}
+
+impl<T: Float> CascadeController<T> {
+    pub fn new(
+        outer: Box<dyn FeedbackController<T>>,
+        inner: Box<dyn FeedbackController<T>>,
+        velocity_source: VelocitySource<T>,
+        feedforward: Option<Box<dyn FeedforwardController<T>>>,
+        max_velocity: Option<T>,
+    ) -> Self {
+        Self {
+            outer,
+            inner,
+            velocity_source,
+            feedforward,
+            velocity_set_point_tracker: DifferentialTracker::new(2),
+            max_velocity: max_velocity.map(|max_velocity| max_velocity.abs()),
+            feedforward_output: T::zero(),
+        }
+    }
+}
+
+impl<T: Float> FeedbackController<T> for CascadeController<T> {
+    fn update(&mut self, set_point: T, process_variable: T) -> T {
+        let mut velocity_set_point = self.outer.update(set_point, process_variable);
+        if let Some(max_velocity) = self.max_velocity {
+            velocity_set_point = velocity_set_point.clamp(-max_velocity, max_velocity);
+        }
+        self.velocity_set_point_tracker.update(velocity_set_point);
+
+        self.feedforward_output = match &mut self.feedforward {
+            Some(feedforward) => feedforward.update(self.velocity_set_point_tracker.clone()),
+            None => T::zero(),
+        };
+        let velocity = self
+            .velocity_source
+            .velocity()
+            .unwrap_or(velocity_set_point);
+        self.inner.update(velocity_set_point, velocity) + self.feedforward_output
+    }
+
+    fn reset(&mut self) {
+        self.outer.reset();
+        self.inner.reset();
+        if let Some(feedforward) = &mut self.feedforward {
+            feedforward.reset();
+        }
+        self.velocity_set_point_tracker.reset();
+        self.feedforward_output = T::zero();
+    }
+
+    /// The telemetry of the inner loop, with the feedforward added.
+    fn telemetry(&self) -> Option<ControllerTelemetry<T>> {
+        self.inner.telemetry().map(|telemetry| ControllerTelemetry {
+            feedforward: telemetry.feedforward + self.feedforward_output,
+            output: telemetry.output + self.feedforward_output,
+            ..telemetry
+        })
+    }
+}
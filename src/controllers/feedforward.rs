@@ -0,0 +1,80 @@
+use num_traits::Float;
+
+use super::FeedforwardController;
+use crate::utils::differential_tracker::DifferentialTracker;
+
+/// A kS/kV/kA feedforward model of a motor driven mechanism.
+///
+/// The output is `kS * sign(v) + kV * v + kA * a + kG`, where `v` and `a` are the
+/// reference velocity and acceleration. It is meant to be summed with the output of a
+/// feedback controller such as [`crate::controllers::pid::PID`]:
+///
+/// ```ignore
+/// let output = feedforward.calculate(reference_velocity, reference_acceleration)
+///     + pid.update(reference_position, measured_position);
+/// ```
+#[derive(Clone)]
+pub struct SimpleMotorFeedforward<T: Float> {
+    /// Struct [`FeedforwardGains`] containing the gains.
+    gains: FeedforwardGains<T>,
+}
+
+#[derive(Clone)]
+pub struct FeedforwardGains<T> {
+    /// Static friction gain.
+    ks: T,
+
+    /// Velocity gain.
+    kv: T,
+
+    /// Acceleration gain.
+    ka: T,
+
+    /// Gravity gain. This is a constant offset, such as for a lift.
+    kg: T,
+}
+
+impl<T: Float> FeedforwardGains<T> {
+    pub fn new(ks: T, kv: T, ka: T, kg: Option<T>) -> Self {
+        Self {
+            ks,
+            kv,
+            ka,
+            kg: kg.unwrap_or(T::zero()),
+        }
+    }
+}
+
+impl<T: Float> SimpleMotorFeedforward<T> {
+    pub fn new(ks: T, kv: T, ka: T, kg: Option<T>) -> Self {
+        Self {
+            gains: FeedforwardGains::new(ks, kv, ka, kg),
+        }
+    }
+    pub fn from_feedforward_gains(gains: FeedforwardGains<T>) -> Self {
+        Self { gains }
+    }
+
+    /// Computes the feedforward output for a reference velocity and acceleration.
+    pub fn calculate(&self, velocity: T, acceleration: T) -> T {
+        let static_friction = if velocity == T::zero() {
+            T::zero()
+        } else {
+            self.gains.ks * velocity.signum()
+        };
+        static_friction + self.gains.kv * velocity + self.gains.ka * acceleration + self.gains.kg
+    }
+}
+
+impl<T: Float> FeedforwardController<T> for SimpleMotorFeedforward<T> {
+    /// The tracker is expected to contain the reference velocity.
+    /// Acceleration is its first derivative, so it is per millisecond like the
+    /// rest of [`DifferentialTracker`], and is zero until two samples exist.
+    fn update(&mut self, differential_tracker: DifferentialTracker<T>) -> T {
+        let velocity = differential_tracker.derivative(0).unwrap_or(T::zero());
+        let acceleration = differential_tracker.derivative(1).unwrap_or(T::zero());
+        self.calculate(velocity, acceleration)
+    }
+
+    fn reset(&mut self) {}
+}
@@ -0,0 +1,94 @@
+use alloc::{rc::Rc, vec::Vec};
+use core::ops::AddAssign;
+
+use num_traits::{Float, FromPrimitive, Zero};
+
+use super::{
+    pid::{PIDGains, PID},
+    ControllerTelemetry, FeedbackController,
+};
+use crate::ilerp;
+
+/// The value that a [`GainScheduledPID`] looks up its gains with.
+#[derive(Clone)]
+pub enum ScheduleVariable<T> {
+    /// The absolute value of the error, such as for turns of different sizes.
+    ErrorMagnitude,
+
+    /// An external value, such as whether a mobile goal is clamped.
+    External(Rc<dyn Fn() -> T>),
+}
+
+/// A [`PID`] whose gains are interpolated from a table keyed on a [`ScheduleVariable`].
+///
+/// Outside of the table's keys, the nearest gains are used.
+/// Windup, sign flip and reset behavior is that of [`PID`].
+#[derive(Clone)]
+pub struct GainScheduledPID<T: Float> {
+    pid: PID<T>,
+
+    /// Pairs of a scheduling variable key and its gains, sorted by key.
+    schedule: Vec<(T, PIDGains<T>)>,
+    schedule_variable: ScheduleVariable<T>,
+}
+
+impl<T: Float + Zero> GainScheduledPID<T> {
+    pub fn new(
+        mut schedule: Vec<(T, PIDGains<T>)>,
+        schedule_variable: ScheduleVariable<T>,
+        windup_range: T,
+        reset_on_sign_flip: bool,
+        differential_tracker_len: usize,
+    ) -> Self {
+        assert!(
+            !schedule.is_empty(),
+            "A gain schedule requires at least one set of gains."
+        );
+        schedule.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+        Self {
+            pid: PID::from_pid_gains(
+                schedule[0].1.clone(),
+                windup_range,
+                reset_on_sign_flip,
+                differential_tracker_len,
+            ),
+            schedule,
+            schedule_variable,
+        }
+    }
+
+    /// The interpolated gains at a value of the scheduling variable.
+    pub fn gains_at(&self, key: T) -> PIDGains<T> {
+        let upper_index = self.schedule.partition_point(|(k, _)| *k <= key);
+        if upper_index == 0 {
+            return self.schedule[0].1.clone();
+        }
+        if upper_index == self.schedule.len() {
+            return self.schedule[upper_index - 1].1.clone();
+        }
+        let (lower_key, lower_gains) = &self.schedule[upper_index - 1];
+        let (upper_key, upper_gains) = &self.schedule[upper_index];
+        lower_gains.lerp(upper_gains, ilerp!(*lower_key, *upper_key, key))
+    }
+}
+
+impl<T: Float + Zero + FromPrimitive + AddAssign + num_traits::Float> FeedbackController<T>
+    for GainScheduledPID<T>
+{
+    fn update(&mut self, set_point: T, process_variable: T) -> T {
+        let key = match &self.schedule_variable {
+            ScheduleVariable::ErrorMagnitude => Float::abs(set_point - process_variable),
+            ScheduleVariable::External(variable) => variable(),
+        };
+        self.pid.set_gains(self.gains_at(key));
+        self.pid.update(set_point, process_variable)
+    }
+
+    fn reset(&mut self) {
+        self.pid.reset();
+    }
+
+    fn telemetry(&self) -> Option<ControllerTelemetry<T>> {
+        self.pid.telemetry()
+    }
+}
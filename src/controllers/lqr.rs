@@ -0,0 +1,264 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use nalgebra::{Matrix2, Matrix2x3, Matrix3, Matrix3x2, SMatrix, SVector, Vector2, Vector3};
+
+use super::PoseTrackingController;
+use crate::differential::pose::Pose;
+
+/// Discretizes the continuous system `ẋ = Ax + Bu` with a zero-order hold on `u`.
+///
+/// The matrix exponential is evaluated by its power series, which converges quickly
+/// for `‖A·dt‖` on the order of a control loop.
+pub fn discretize<const N: usize, const M: usize>(
+    a: &SMatrix<f64, N, N>,
+    b: &SMatrix<f64, N, M>,
+    dt: Duration,
+) -> (SMatrix<f64, N, N>, SMatrix<f64, N, M>) {
+    let dt = dt.as_secs_f64();
+    let a_dt = a * dt;
+
+    // A_d = Σ (A·dt)^k / k!, B_d = Σ (A·dt)^k / (k + 1)! · dt · B.
+    let mut term = SMatrix::<f64, N, N>::identity();
+    let mut a_discrete = term;
+    let mut b_integral = term;
+    for k in 1..30 {
+        term = term * a_dt / k as f64;
+        a_discrete += term;
+        b_integral += term / (k + 1) as f64;
+        if term.norm() < 1e-14 {
+            break;
+        }
+    }
+    (a_discrete, b_integral * dt * b)
+}
+
+/// Solves the discrete algebraic Riccati equation
+/// `P = AᵀPA - AᵀPB(R + BᵀPB)⁻¹BᵀPA + Q` with the structure-preserving doubling algorithm.
+///
+/// Returns `None` if an inverse does not exist or if it does not converge,
+/// such as when `(A, B)` is not stabilizable.
+pub fn solve_dare<const N: usize, const M: usize>(
+    a: &SMatrix<f64, N, N>,
+    b: &SMatrix<f64, N, M>,
+    q: &SMatrix<f64, N, N>,
+    r: &SMatrix<f64, M, M>,
+) -> Option<SMatrix<f64, N, N>> {
+    const MAX_ITERATIONS: usize = 100;
+    const TOLERANCE: f64 = 1e-10;
+
+    let identity = SMatrix::<f64, N, N>::identity();
+    let mut a_k = *a;
+    let mut g_k = b * r.try_inverse()? * b.transpose();
+    let mut h_k = *q;
+    for _ in 0..MAX_ITERATIONS {
+        let w = (identity + g_k * h_k).try_inverse()?;
+        let a_next = a_k * w * a_k;
+        let g_next = g_k + a_k * w * g_k * a_k.transpose();
+        let h_next = h_k + a_k.transpose() * h_k * w * a_k;
+
+        let converged = (h_next - h_k).norm() <= TOLERANCE * h_next.norm().max(1.0);
+        a_k = a_next;
+        g_k = g_next;
+        h_k = h_next;
+        if converged {
+            // Symmetrize to remove round-off.
+            return Some((h_k + h_k.transpose()) / 2.0);
+        }
+    }
+    None
+}
+
+/// Bryson's rule: a diagonal cost matrix that weighs each component by the inverse
+/// square of its maximum acceptable value.
+pub fn bryson_cost<const N: usize>(max_values: &SVector<f64, N>) -> SMatrix<f64, N, N> {
+    SMatrix::from_diagonal(&max_values.map(|max_value| 1.0 / (max_value * max_value)))
+}
+
+/// The optimal gain `K = (R + BᵀPB)⁻¹BᵀPA` of a discrete system.
+fn lqr_gain<const N: usize, const M: usize>(
+    a: &SMatrix<f64, N, N>,
+    b: &SMatrix<f64, N, M>,
+    q: &SMatrix<f64, N, N>,
+    r: &SMatrix<f64, M, M>,
+) -> Option<SMatrix<f64, M, N>> {
+    let p = solve_dare(a, b, q, r)?;
+    Some((r + b.transpose() * p * b).try_inverse()? * b.transpose() * p * a)
+}
+
+/// A linear time-invariant model of a differential drive.
+///
+/// The states are the linear and angular velocity, and the inputs are the left and
+/// right voltages. Counterclockwise rotation is positive.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DifferentialDriveModel {
+    pub a: Matrix2<f64>,
+    pub b: Matrix2<f64>,
+}
+
+impl DifferentialDriveModel {
+    pub fn new(a: Matrix2<f64>, b: Matrix2<f64>) -> Self {
+        Self { a, b }
+    }
+
+    /// Builds the model from characterized feedforward constants.
+    ///
+    /// `kv` is in volts per unit of velocity and `ka` in volts per unit of acceleration,
+    /// where the linear velocity uses any distance unit per second and the angular
+    /// velocity uses radians per second. The angular voltage is half of the
+    /// difference between the right and left voltages.
+    pub fn from_characterization(
+        kv_linear: f64,
+        ka_linear: f64,
+        kv_angular: f64,
+        ka_angular: f64,
+    ) -> Self {
+        assert!(
+            ka_linear > 0.0 && ka_angular > 0.0,
+            "Acceleration gains must be positive."
+        );
+        Self {
+            a: Matrix2::new(-kv_linear / ka_linear, 0.0, 0.0, -kv_angular / ka_angular),
+            b: Matrix2::new(
+                0.5 / ka_linear,
+                0.5 / ka_linear,
+                -0.5 / ka_angular,
+                0.5 / ka_angular,
+            ),
+        }
+    }
+}
+
+/// A linear-quadratic regulator for the velocity of a [`DifferentialDriveModel`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DifferentialDriveLQR {
+    gain: Matrix2<f64>,
+}
+
+impl DifferentialDriveLQR {
+    /// Discretizes the model with the loop period `dt` and solves for the optimal gain.
+    ///
+    /// `max_error` holds the maximum acceptable linear and angular velocity errors, and
+    /// `max_voltage` the maximum acceptable left and right voltages (Bryson's rule).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Riccati equation has no solution for the model.
+    pub fn new(
+        model: &DifferentialDriveModel,
+        max_error: Vector2<f64>,
+        max_voltage: Vector2<f64>,
+        dt: Duration,
+    ) -> Self {
+        let (a, b) = discretize(&model.a, &model.b, dt);
+        Self {
+            gain: lqr_gain(&a, &b, &bryson_cost(&max_error), &bryson_cost(&max_voltage))
+                .expect("The Riccati equation has no solution for the model."),
+        }
+    }
+
+    pub fn gain(&self) -> &Matrix2<f64> {
+        &self.gain
+    }
+
+    /// Returns the left and right voltages that drive `state` towards `reference`.
+    pub fn calculate(&self, state: Vector2<f64>, reference: Vector2<f64>) -> (f64, f64) {
+        let voltages = self.gain * (reference - state);
+        (voltages.x, voltages.y)
+    }
+}
+
+/// A linear time-varying LQR that tracks a reference pose, as an alternative to RAMSETE.
+///
+/// The pose error in the robot's frame, `[x, y, θ]`, is linearized about the reference
+/// linear velocity `v`, giving `ẏ = vθ`. Gains are precomputed for a table of
+/// velocities and interpolated, so no Riccati equation is solved while tracking.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UnicycleLQR {
+    max_velocity: f64,
+
+    /// Gains at evenly spaced velocities from `-max_velocity` to `max_velocity`.
+    gains: Vec<Matrix2x3<f64>>,
+}
+
+impl UnicycleLQR {
+    const TABLE_SIZE: usize = 51;
+
+    /// `max_error` holds the maximum acceptable x, y and angular errors, and `max_effort`
+    /// the maximum acceptable linear and angular velocity corrections (Bryson's rule).
+    /// The reference velocities are expected in the same units as `max_velocity`.
+    pub fn new(
+        max_error: Vector3<f64>,
+        max_effort: Vector2<f64>,
+        max_velocity: f64,
+        dt: Duration,
+    ) -> Self {
+        assert!(max_velocity > 0.0, "Maximum velocity must be positive.");
+        let q = bryson_cost(&max_error);
+        let r = bryson_cost(&max_effort);
+        let b = Matrix3x2::new(1.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+
+        // The lateral error is uncontrollable when stopped, but the x and angular
+        // errors are still driven directly by the inputs. The gain there regulates
+        // only those two.
+        let stationary_gain = {
+            let q = Matrix2::new(q[(0, 0)], 0.0, 0.0, q[(2, 2)]);
+            let (a, b) = discretize(&Matrix2::zeros(), &Matrix2::identity(), dt);
+            let gain = lqr_gain(&a, &b, &q, &r).unwrap_or_else(Matrix2::zeros);
+            Matrix2x3::new(
+                gain[(0, 0)],
+                0.0,
+                gain[(0, 1)],
+                gain[(1, 0)],
+                0.0,
+                gain[(1, 1)],
+            )
+        };
+        let gains = (0..Self::TABLE_SIZE)
+            .map(|i| {
+                let velocity =
+                    -max_velocity + 2.0 * max_velocity * i as f64 / (Self::TABLE_SIZE - 1) as f64;
+                if velocity.abs() < 1e-4 {
+                    return stationary_gain;
+                }
+                let a = Matrix3::new(0.0, 0.0, 0.0, 0.0, 0.0, velocity, 0.0, 0.0, 0.0);
+                let (a, b) = discretize(&a, &b, dt);
+                lqr_gain(&a, &b, &q, &r).unwrap_or(stationary_gain)
+            })
+            .collect();
+        Self {
+            max_velocity,
+            gains,
+        }
+    }
+
+    /// The interpolated gain at a reference linear velocity.
+    pub fn gain_at(&self, velocity: f64) -> Matrix2x3<f64> {
+        let position = (velocity.clamp(-self.max_velocity, self.max_velocity) + self.max_velocity)
+            / (2.0 * self.max_velocity)
+            * (Self::TABLE_SIZE - 1) as f64;
+        let lower_index = (position as usize).min(Self::TABLE_SIZE - 2);
+        let t = position - lower_index as f64;
+        self.gains[lower_index] * (1.0 - t) + self.gains[lower_index + 1] * t
+    }
+}
+
+impl PoseTrackingController for UnicycleLQR {
+    fn calculate(
+        &mut self,
+        local_error: Pose,
+        reference_linear_velocity: f64,
+        reference_angular_velocity: f64,
+    ) -> (f64, f64) {
+        let correction = self.gain_at(reference_linear_velocity)
+            * Vector3::new(
+                local_error.position.x,
+                local_error.position.y,
+                local_error.orientation,
+            );
+        (
+            reference_linear_velocity + correction.x,
+            reference_angular_velocity + correction.y,
+        )
+    }
+}
@@ -1,11 +1,78 @@
 use num_traits::Float;
 
-use crate::utils::differential_tracker::DifferentialTracker;
+use crate::{differential::pose::Pose, utils::differential_tracker::DifferentialTracker};
+
+/// A snapshot of the last update of a [`FeedbackController`], for tuning.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ControllerTelemetry<T> {
+    pub error: T,
+    pub proportional: T,
+    pub integral: T,
+    pub derivative: T,
+
+    /// Any contribution outside of the three terms, such as a feedforward.
+    pub feedforward: T,
+
+    /// The accumulated integral, before being scaled by its gain.
+    pub integral_state: T,
+    pub output: T,
+
+    /// Whether the output was clamped to a limit.
+    pub saturated: bool,
+
+    /// Whether the integral was reset during the update.
+    pub integral_reset: bool,
+}
+
+impl<T: Float> ControllerTelemetry<T> {
+    /// A snapshot where the whole output comes from a single term.
+    pub fn from_output(error: T, output: T) -> Self {
+        Self {
+            error,
+            proportional: T::zero(),
+            integral: T::zero(),
+            derivative: T::zero(),
+            feedforward: T::zero(),
+            integral_state: T::zero(),
+            output,
+            saturated: false,
+            integral_reset: false,
+        }
+    }
+}
+
+impl<T: core::fmt::Display> core::fmt::Display for ControllerTelemetry<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "e: {}, p: {}, i: {}, d: {}, ff: {}, integral: {}, out: {}{}{}",
+            self.error,
+            self.proportional,
+            self.integral,
+            self.derivative,
+            self.feedforward,
+            self.integral_state,
+            self.output,
+            if self.saturated { " (saturated)" } else { "" },
+            if self.integral_reset {
+                " (integral reset)"
+            } else {
+                ""
+            },
+        )
+    }
+}
 
 dyn_clone::clone_trait_object!(<T> FeedbackController<T>);
 pub trait FeedbackController<T: Float>: dyn_clone::DynClone {
     fn update(&mut self, set_point: T, process_variable: T) -> T;
     fn reset(&mut self);
+
+    /// A snapshot of the last update, if the controller supports it and has been updated
+    /// since its last reset.
+    fn telemetry(&self) -> Option<ControllerTelemetry<T>> {
+        None
+    }
 }
 
 dyn_clone::clone_trait_object!(<T> FeedforwardController<T>);
@@ -14,4 +81,28 @@ pub trait FeedforwardController<T: Float>: dyn_clone::DynClone {
     fn reset(&mut self);
 }
 
+dyn_clone::clone_trait_object!(PoseTrackingController);
+/// Tracks a reference pose moving at reference velocities, such as along a trajectory.
+pub trait PoseTrackingController: dyn_clone::DynClone {
+    /// Returns the linear and angular velocity to apply, given the reference pose
+    /// relative to the robot's frame.
+    fn calculate(
+        &mut self,
+        local_error: Pose,
+        reference_linear_velocity: f64,
+        reference_angular_velocity: f64,
+    ) -> (f64, f64);
+    fn reset(&mut self) {}
+}
+
+#[macro_use]
+pub mod autotune;
+pub mod bang_bang;
+pub mod cascade;
+pub mod feedforward;
+pub mod gain_scheduled_pid;
+pub mod lqr;
 pub mod pid;
+pub mod profiled_pid;
+pub mod ramsete;
+pub mod take_back_half;
@@ -1,9 +1,17 @@
+use alloc::{boxed::Box, rc::Rc};
 use core::ops::AddAssign;
 
 use num_traits::{Float, FromPrimitive, Zero};
 
-use super::FeedbackController;
-use crate::utils::differential_tracker::DifferentialTracker;
+use super::{ControllerTelemetry, FeedbackController};
+use crate::{
+    lerp,
+    utils::{
+        clock::Clock,
+        differential_tracker::{DerivativeMethod, DifferentialTracker},
+        filters::Filter,
+    },
+};
 pub struct PID<T: Float> {
     /// Struct [`PIDGains`] containing the gains.
     gains: PIDGains<T>,
@@ -16,15 +24,65 @@ pub struct PID<T: Float> {
     reset_on_sign_flip: bool,
 
     differential_tracker: DifferentialTracker<T>,
+
+    /// Whether the derivative is taken of the process variable instead of the error.
+    /// This avoids derivative kick when the set point changes.
+    derivative_on_measurement: bool,
+    measurement_tracker: DifferentialTracker<T>,
+
+    /// Time constant of the first-order low-pass filter on the derivative, in milliseconds.
+    derivative_filter_time_constant: Option<T>,
+    filtered_derivative: Option<T>,
+
+    /// Applied to the raw derivative before the low-pass filter, if any.
+    derivative_smoother: Option<Box<dyn Filter<T>>>,
+
+    /// The minimum and maximum output.
+    output_limits: Option<(T, T)>,
+    anti_windup: AntiWindup<T>,
+
+    telemetry: Option<ControllerTelemetry<T>>,
 }
 
-#[derive(Clone)]
+/// How the integral is kept from winding up while the output is saturated.
+///
+/// Only applies when the [`PID`] has output limits.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AntiWindup<T> {
+    /// Only `windup_range` and the sign flip reset are used.
+    None,
+
+    /// The integral stops accumulating while it would push the output further
+    /// into saturation.
+    ConditionalIntegration,
+
+    /// The integral is driven back by the difference between the saturated and
+    /// unsaturated outputs, scaled by this tracking gain.
+    BackCalculation(T),
+}
+
+#[derive(Clone, Debug)]
 pub struct PIDGains<T> {
     kp: T, // Proportional gain
     ki: T, // Integral gain
     kd: T, // Derivative gain
 }
 
+impl<T: Float> PIDGains<T> {
+    pub fn new(kp: T, ki: T, kd: T) -> Self {
+        Self { kp, ki, kd }
+    }
+
+    /// Linearly interpolates each gain towards `other`.
+    pub fn lerp(&self, other: &Self, t: T) -> Self {
+        Self {
+            kp: lerp!(self.kp, other.kp, t),
+            ki: lerp!(self.ki, other.ki, t),
+            kd: lerp!(self.kd, other.kd, t),
+        }
+    }
+}
+
 impl<T: Float + Zero> PID<T> {
     pub fn new(
         kp: T,
@@ -34,16 +92,12 @@ impl<T: Float + Zero> PID<T> {
         reset_on_sign_flip: bool,
         differential_tracker_len: usize,
     ) -> Self {
-        assert!(
-            differential_tracker_len >= 2,
-            "Differential tracker length must be at least 2 to support a derivative."
-        );
-        Self {
-            gains: PIDGains { kp, ki, kd },
-            reset_on_sign_flip,
+        Self::from_pid_gains(
+            PIDGains { kp, ki, kd },
             windup_range,
-            differential_tracker: DifferentialTracker::new(differential_tracker_len),
-        }
+            reset_on_sign_flip,
+            differential_tracker_len,
+        )
     }
     pub fn from_pid_gains(
         gains: PIDGains<T>,
@@ -60,17 +114,86 @@ impl<T: Float + Zero> PID<T> {
             reset_on_sign_flip,
             windup_range,
             differential_tracker: DifferentialTracker::new(differential_tracker_len),
+            derivative_on_measurement: false,
+            measurement_tracker: DifferentialTracker::new(differential_tracker_len),
+            derivative_filter_time_constant: None,
+            filtered_derivative: None,
+            derivative_smoother: None,
+            output_limits: None,
+            anti_windup: AntiWindup::None,
+            telemetry: None,
         }
     }
+
+    /// Differentiates the process variable instead of the error.
+    pub fn with_derivative_on_measurement(mut self, derivative_on_measurement: bool) -> Self {
+        self.derivative_on_measurement = derivative_on_measurement;
+        self
+    }
+
+    /// Low-pass filters the derivative with a time constant in milliseconds.
+    pub fn with_derivative_filter(mut self, time_constant: T) -> Self {
+        self.derivative_filter_time_constant = Some(time_constant);
+        self
+    }
+
+    /// Estimates the derivative with `derivative_method`, such as a least-squares fit
+    /// over the whole `differential_tracker_len` window.
+    pub fn with_derivative_method(mut self, derivative_method: DerivativeMethod) -> Self {
+        self.differential_tracker = self
+            .differential_tracker
+            .with_derivative_method(derivative_method);
+        self.measurement_tracker = self
+            .measurement_tracker
+            .with_derivative_method(derivative_method);
+        self
+    }
+
+    /// Smooths the derivative with any [`Filter`], such as a
+    /// [`crate::utils::filters::median::MedianFilter`] to reject spikes from sensor noise.
+    pub fn with_derivative_smoother(mut self, smoother: Box<dyn Filter<T>>) -> Self {
+        self.derivative_smoother = Some(smoother);
+        self
+    }
+
+    /// Clamps the output of the controller.
+    pub fn with_output_limits(mut self, min: T, max: T) -> Self {
+        assert!(min <= max, "Minimum output may not exceed the maximum.");
+        self.output_limits = Some((min, max));
+        self
+    }
+
+    /// Timestamps the error and measurement with `clock`, such as a
+    /// [`crate::utils::clock::MockClock`] for exact integrals and derivatives.
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.differential_tracker = self.differential_tracker.with_clock(clock.clone());
+        self.measurement_tracker = self.measurement_tracker.with_clock(clock);
+        self
+    }
+
+    /// Sets the anti-windup strategy used while the output is saturated.
+    pub fn with_anti_windup(mut self, anti_windup: AntiWindup<T>) -> Self {
+        self.anti_windup = anti_windup;
+        self
+    }
+    pub fn gains(&self) -> &PIDGains<T> {
+        &self.gains
+    }
+
+    /// Replaces the gains without resetting the integral or derivative state.
+    pub fn set_gains(&mut self, gains: PIDGains<T>) {
+        self.gains = gains;
+    }
 }
 impl<T: Float + Zero + FromPrimitive + AddAssign + num_traits::Float> FeedbackController<T>
     for PID<T>
 {
     fn update(&mut self, set_point: T, process_variable: T) -> T {
         let error = set_point - process_variable;
+        let mut previous_integral = self.differential_tracker.integral();
         self.differential_tracker.update(error);
 
-        if Float::signum(error)
+        let integral_reset = if Float::signum(error)
             != Float::signum({
                 if let Some(prev_error) = self
                     .differential_tracker
@@ -85,15 +208,94 @@ impl<T: Float + Zero + FromPrimitive + AddAssign + num_traits::Float> FeedbackCo
             || self.windup_range != T::zero() && Float::abs(error) > self.windup_range
         {
             self.differential_tracker.reset_integral();
+            previous_integral = T::zero();
+            true
+        } else {
+            false
+        };
+
+        let raw_derivative: T = if self.derivative_on_measurement {
+            self.measurement_tracker.update(process_variable);
+            -self.measurement_tracker.derivative(1).unwrap_or(T::zero())
+        } else {
+            self.differential_tracker.derivative(1).unwrap_or(T::zero())
+        };
+        let raw_derivative = match &mut self.derivative_smoother {
+            Some(smoother) => smoother.update(raw_derivative),
+            None => raw_derivative,
+        };
+        let derivative = match (
+            self.derivative_filter_time_constant,
+            self.filtered_derivative,
+            self.differential_tracker.delta_time(),
+        ) {
+            (Some(time_constant), Some(filtered_derivative), Some(dt)) => {
+                let alpha = dt / (time_constant + dt);
+                filtered_derivative + alpha * (raw_derivative - filtered_derivative)
+            }
+            _ => raw_derivative,
+        };
+        self.filtered_derivative = Some(derivative);
+
+        let proportional = self.gains.kp * error;
+        let integral = self.gains.ki * self.differential_tracker.integral();
+        let derivative = self.gains.kd * derivative;
+        let output = proportional + integral + derivative;
+        let mut telemetry = ControllerTelemetry {
+            error,
+            proportional,
+            integral,
+            derivative,
+            feedforward: T::zero(),
+            integral_state: self.differential_tracker.integral(),
+            output,
+            saturated: false,
+            integral_reset,
+        };
+        let Some((min_output, max_output)) = self.output_limits else {
+            self.telemetry = Some(telemetry);
+            return output;
+        };
+        let saturated_output = output.clamp(min_output, max_output);
+        match self.anti_windup {
+            AntiWindup::None => {}
+            AntiWindup::ConditionalIntegration => {
+                if output > max_output && error > T::zero()
+                    || output < min_output && error < T::zero()
+                {
+                    self.differential_tracker.set_integral(previous_integral);
+                }
+            }
+            AntiWindup::BackCalculation(tracking_gain) => {
+                if let Some(dt) = self.differential_tracker.delta_time() {
+                    if self.gains.ki != T::zero() {
+                        self.differential_tracker.set_integral(
+                            self.differential_tracker.integral()
+                                + tracking_gain * (saturated_output - output) * dt / self.gains.ki,
+                        );
+                    }
+                }
+            }
         }
-        let derivative: T = self.differential_tracker.derivative(1).unwrap_or(T::zero());
-        self.gains.kp * error
-            + self.gains.ki * self.differential_tracker.integral()
-            + self.gains.kd * derivative
+        telemetry.output = saturated_output;
+        telemetry.saturated = saturated_output != output;
+        telemetry.integral_state = self.differential_tracker.integral();
+        self.telemetry = Some(telemetry);
+        saturated_output
     }
 
     fn reset(&mut self) {
         self.differential_tracker.reset();
+        self.measurement_tracker.reset();
+        self.filtered_derivative = None;
+        if let Some(smoother) = &mut self.derivative_smoother {
+            smoother.reset();
+        }
+        self.telemetry = None;
+    }
+
+    fn telemetry(&self) -> Option<ControllerTelemetry<T>> {
+        self.telemetry
     }
 }
 
@@ -103,7 +305,26 @@ impl<T: num_traits::Float> Clone for PID<T> {
             gains: self.gains.clone(),
             windup_range: self.windup_range,
             reset_on_sign_flip: self.reset_on_sign_flip,
-            differential_tracker: DifferentialTracker::new(self.differential_tracker.space_size()),
+            differential_tracker: {
+                let mut differential_tracker = self.differential_tracker.clone();
+                differential_tracker.reset();
+                differential_tracker
+            },
+            derivative_on_measurement: self.derivative_on_measurement,
+            measurement_tracker: {
+                let mut measurement_tracker = self.measurement_tracker.clone();
+                measurement_tracker.reset();
+                measurement_tracker
+            },
+            derivative_filter_time_constant: self.derivative_filter_time_constant,
+            filtered_derivative: None,
+            derivative_smoother: self.derivative_smoother.clone().map(|mut smoother| {
+                smoother.reset();
+                smoother
+            }),
+            output_limits: self.output_limits,
+            anti_windup: self.anti_windup,
+            telemetry: None,
         }
     }
 }
@@ -0,0 +1,196 @@
+use alloc::{boxed::Box, rc::Rc};
+use core::{ops::AddAssign, time::Duration};
+
+use num_traits::{Float, FromPrimitive, Zero};
+
+use super::{pid::PID, ControllerTelemetry, FeedbackController, FeedforwardController};
+use crate::utils::{
+    clock::{vex_clock, Clock},
+    differential_tracker::DifferentialTracker,
+};
+
+/// A [`PID`] that tracks a trapezoidal motion profile instead of stepping to the goal.
+///
+/// The profile starts at the error of the first update after a reset and approaches
+/// zero error, limited by `max_velocity` and `max_acceleration` in units of the error
+/// per second. Since it profiles the remaining error rather than an absolute position,
+/// it works both with `update(target, measurement)` and with the `update(error, 0.0)`
+/// calls of the motions, so it can be used as any of their controllers.
+///
+/// The goal can only be seen through the error, so a change of goal shows as the error
+/// jumping by more than the profile allows in one update. The profile is then planned
+/// again from the measured error, keeping its speed if the error has the same sign.
+///
+/// If a feedforward is given, it is fed the velocity of the profiled set point and its
+/// output is added to that of the PID.
+///
+/// # Example
+///
+/// ```
+/// let angular_controller = Box::new(ProfiledPID::new(
+///     PID::new(1.5, 0.0, 8.0, 0.0, true, 2),
+///     8.0,  // Radians per second.
+///     20.0, // Radians per second squared.
+/// ));
+/// ```
+#[derive(Clone)]
+pub struct ProfiledPID<T: Float> {
+    pid: PID<T>,
+    max_velocity: T,
+    max_acceleration: T,
+    feedforward: Option<Box<dyn FeedforwardController<T>>>,
+
+    /// The profiled error and the speed at which it approaches zero.
+    reference: Option<(T, T)>,
+    previous_error: Option<T>,
+    previous_time: Option<Duration>,
+    clock: Rc<dyn Clock>,
+
+    /// Tracks the velocity of the set point for the feedforward.
+    set_point_velocity_tracker: DifferentialTracker<T>,
+    feedforward_output: T,
+}
+
+impl<T: Float + Zero> ProfiledPID<T> {
+    pub fn new(pid: PID<T>, max_velocity: T, max_acceleration: T) -> Self {
+        assert!(
+            max_velocity > T::zero() && max_acceleration > T::zero(),
+            "Maximum velocity and acceleration must be positive."
+        );
+        Self {
+            pid,
+            max_velocity,
+            max_acceleration,
+            feedforward: None,
+            reference: None,
+            previous_error: None,
+            previous_time: None,
+            clock: vex_clock(),
+            set_point_velocity_tracker: DifferentialTracker::new(2),
+            feedforward_output: T::zero(),
+        }
+    }
+
+    pub fn with_feedforward(mut self, feedforward: Box<dyn FeedforwardController<T>>) -> Self {
+        self.feedforward = Some(feedforward);
+        self
+    }
+
+    /// Advances the profile and the PID with `clock`.
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.pid = self.pid.with_clock(clock.clone());
+        self.set_point_velocity_tracker = self.set_point_velocity_tracker.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// The error that the profile currently expects.
+    pub fn reference_error(&self) -> Option<T> {
+        self.reference.map(|(error, _)| error)
+    }
+
+    /// The velocity of the profiled set point in the direction of the process variable,
+    /// in units per second.
+    pub fn set_point_velocity(&self) -> T {
+        self.reference
+            .map(|(error, speed)| error.signum() * speed)
+            .unwrap_or(T::zero())
+    }
+
+    /// Whether the profile has reached zero error.
+    pub fn is_profile_done(&self) -> bool {
+        self.reference_error() == Some(T::zero())
+    }
+
+    /// Plans the profile again from `error` if it has moved further from the last error
+    /// than the maximum velocity allows in `dt` seconds, such as when the goal changes.
+    fn replan_on_jump(&mut self, error: T, dt: T) {
+        let (Some(previous_error), Some((reference_error, speed))) =
+            (self.previous_error, self.reference)
+        else {
+            return;
+        };
+        let two = T::one() + T::one();
+        if (error - previous_error).abs() <= two * self.max_velocity * dt {
+            return;
+        }
+        let speed = if error.signum() == reference_error.signum() {
+            speed
+        } else {
+            T::zero()
+        };
+        self.reference = Some((error, speed));
+    }
+
+    /// Advances the profile by `dt` seconds.
+    fn step_profile(&mut self, dt: T) {
+        let Some((error, speed)) = self.reference else {
+            return;
+        };
+        let distance = error.abs();
+        let two = T::one() + T::one();
+
+        // Accelerate up to the maximum velocity, but no faster than can still
+        // decelerate to a stop over the remaining distance.
+        let speed = (speed + self.max_acceleration * dt)
+            .min(self.max_velocity)
+            .min((two * self.max_acceleration * distance).sqrt());
+        let step = speed * dt;
+        self.reference = Some(if step >= distance {
+            (T::zero(), T::zero())
+        } else {
+            (error - error.signum() * step, speed)
+        });
+    }
+}
+
+impl<T: Float + Zero + FromPrimitive + AddAssign + num_traits::Float> FeedbackController<T>
+    for ProfiledPID<T>
+{
+    fn update(&mut self, set_point: T, process_variable: T) -> T {
+        let error = set_point - process_variable;
+        let now = self.clock.now();
+        match self.previous_time {
+            Some(previous_time) => {
+                let dt = T::from(now.saturating_sub(previous_time).as_secs_f64()).unwrap();
+                self.replan_on_jump(error, dt);
+                self.step_profile(dt);
+            }
+            None => self.reference = Some((error, T::zero())),
+        }
+        self.previous_error = Some(error);
+        self.previous_time = Some(now);
+
+        let set_point_velocity = self.set_point_velocity();
+        self.set_point_velocity_tracker.update(set_point_velocity);
+        self.feedforward_output = match &mut self.feedforward {
+            Some(feedforward) => feedforward.update(self.set_point_velocity_tracker.clone()),
+            None => T::zero(),
+        };
+
+        // Drive the error towards the profiled error.
+        let reference_error = self.reference_error().unwrap_or(error);
+        self.pid.update(error, reference_error) + self.feedforward_output
+    }
+
+    fn reset(&mut self) {
+        self.pid.reset();
+        if let Some(feedforward) = &mut self.feedforward {
+            feedforward.reset();
+        }
+        self.reference = None;
+        self.previous_error = None;
+        self.previous_time = None;
+        self.set_point_velocity_tracker.reset();
+        self.feedforward_output = T::zero();
+    }
+
+    /// The error is that of the PID, the distance from the profiled set point.
+    fn telemetry(&self) -> Option<ControllerTelemetry<T>> {
+        self.pid.telemetry().map(|telemetry| ControllerTelemetry {
+            feedforward: telemetry.feedforward + self.feedforward_output,
+            output: telemetry.output + self.feedforward_output,
+            ..telemetry
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use num_traits::Float;
+
+use super::PoseTrackingController;
+use crate::differential::pose::Pose;
+
+/// The nonlinear RAMSETE controller for tracking a trajectory with a differential drive.
+///
+/// With the error in the robot's frame, the velocities are
+/// `v = v_d cos(e_θ) + k e_x` and `ω = ω_d + k e_θ + b v_d sinc(e_θ) e_y`,
+/// where `k = 2ζ sqrt(ω_d² + b v_d²)`.
+///
+/// Distances are in inches, so `b` is in rad²/in². The usual `b = 2` for meters is
+/// about `0.0013` here.
+///
+/// # Example
+///
+/// ```
+/// let tracking_controller = Box::new(RAMSETE::new(0.0013, 0.7));
+/// ```
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RAMSETE {
+    /// Like a proportional term, where larger values converge more aggressively.
+    b: f64,
+
+    /// Like a damping term, between 0 and 1.
+    zeta: f64,
+}
+
+impl RAMSETE {
+    pub fn new(b: f64, zeta: f64) -> Self {
+        assert!(b > 0.0, "b must be positive.");
+        assert!(zeta > 0.0 && zeta < 1.0, "ζ must be between 0 and 1.");
+        Self { b, zeta }
+    }
+
+    pub fn b(&self) -> f64 {
+        self.b
+    }
+
+    pub fn zeta(&self) -> f64 {
+        self.zeta
+    }
+}
+
+impl PoseTrackingController for RAMSETE {
+    fn calculate(
+        &mut self,
+        local_error: Pose,
+        reference_linear_velocity: f64,
+        reference_angular_velocity: f64,
+    ) -> (f64, f64) {
+        let gain = 2.0
+            * self.zeta
+            * (reference_angular_velocity.powi(2) + self.b * reference_linear_velocity.powi(2))
+                .sqrt();
+        let sinc = if local_error.orientation.abs() < 1e-9 {
+            1.0
+        } else {
+            local_error.orientation.sin() / local_error.orientation
+        };
+        (
+            reference_linear_velocity * local_error.orientation.cos()
+                + gain * local_error.position.x,
+            reference_angular_velocity
+                + gain * local_error.orientation
+                + self.b * reference_linear_velocity * sinc * local_error.position.y,
+        )
+    }
+}
@@ -0,0 +1,91 @@
+use num_traits::Float;
+
+use super::{ControllerTelemetry, FeedbackController};
+
+/// A take-back-half velocity controller, such as for a flywheel.
+///
+/// The output integrates the error. Each time the error crosses zero, the output
+/// is set halfway between itself and the output at the previous crossing,
+/// which quickly converges on the output that holds the set point.
+#[derive(Clone)]
+pub struct TakeBackHalf<T: Float> {
+    /// How much the output changes per unit of error per update.
+    gain: T,
+    min_output: T,
+    max_output: T,
+
+    /// Used at the first zero crossing instead of halving, if set.
+    /// A good estimate is the output that roughly holds the set point.
+    initial_estimate: Option<T>,
+
+    output: T,
+
+    /// The output at the previous zero crossing.
+    take_back_half: Option<T>,
+    previous_error: Option<T>,
+
+    telemetry: Option<ControllerTelemetry<T>>,
+}
+
+impl<T: Float> TakeBackHalf<T> {
+    pub fn new(gain: T, min_output: T, max_output: T, initial_estimate: Option<T>) -> Self {
+        assert!(
+            min_output <= max_output,
+            "Minimum output may not exceed the maximum."
+        );
+        Self {
+            gain,
+            min_output,
+            max_output,
+            initial_estimate,
+            output: T::zero(),
+            take_back_half: None,
+            previous_error: None,
+            telemetry: None,
+        }
+    }
+}
+
+impl<T: Float> FeedbackController<T> for TakeBackHalf<T> {
+    fn update(&mut self, set_point: T, process_variable: T) -> T {
+        let error = set_point - process_variable;
+        let unclamped_output = self.output + self.gain * error;
+        self.output = unclamped_output.clamp(self.min_output, self.max_output);
+
+        let mut took_back_half = false;
+        if let Some(previous_error) = self.previous_error {
+            if error.signum() != previous_error.signum() {
+                took_back_half = true;
+                self.output = match (self.take_back_half, self.initial_estimate) {
+                    (Some(take_back_half), _) => {
+                        (self.output + take_back_half) / T::from(2.0).unwrap()
+                    }
+                    (None, Some(initial_estimate)) => initial_estimate,
+                    (None, None) => self.output,
+                };
+                self.take_back_half = Some(self.output);
+            }
+        }
+        self.previous_error = Some(error);
+        // The output is the integral of the error.
+        self.telemetry = Some(ControllerTelemetry {
+            integral: self.output,
+            integral_state: self.output,
+            saturated: unclamped_output != self.output && !took_back_half,
+            integral_reset: took_back_half,
+            ..ControllerTelemetry::from_output(error, self.output)
+        });
+        self.output
+    }
+
+    fn reset(&mut self) {
+        self.output = T::zero();
+        self.take_back_half = None;
+        self.previous_error = None;
+        self.telemetry = None;
+    }
+
+    fn telemetry(&self) -> Option<ControllerTelemetry<T>> {
+        self.telemetry
+    }
+}
@@ -2,13 +2,18 @@ use alloc::{boxed::Box, rc::Rc};
 use core::{cell::RefCell, time::Duration};
 
 use nalgebra::Vector3;
-use vexide::{prelude::Motor, sync::Mutex};
+use vexide::sync::Mutex;
 
 use super::{
     drive_curve::DriveCurve,
+    driver::{
+        CurvatureDriveState, DriverOutputState, DriverSlewParameters, HeadingHoldSettings,
+        TractionControlParameters,
+    },
     motions::{
         angular::TurnToSettings, boomerang::BoomerangSettings, linear::MoveToPointSettings,
-        ramsete::RAMSETEHybridSettings, MotionHandler,
+        pure_pursuit::PurePursuitSettings, ramsete::RAMSETEHybridSettings,
+        trajectory::FollowTrajectorySettings, MotionHandler,
     },
     pose::Pose,
 };
@@ -36,6 +41,8 @@ pub struct MotionSettings {
     pub turn_to_settings: RefCell<TurnToSettings>,
     pub boomerang_settings: RefCell<BoomerangSettings>,
     pub ramsete_hybrid_settings: RefCell<RAMSETEHybridSettings>,
+    pub pure_pursuit_settings: RefCell<PurePursuitSettings>,
+    pub follow_trajectory_settings: RefCell<FollowTrajectorySettings>,
 }
 
 impl MotionSettings {
@@ -44,12 +51,16 @@ impl MotionSettings {
         turn_to_settings: RefCell<TurnToSettings>,
         boomerang_settings: RefCell<BoomerangSettings>,
         ramsete_hybrid_settings: RefCell<RAMSETEHybridSettings>,
+        pure_pursuit_settings: RefCell<PurePursuitSettings>,
+        follow_trajectory_settings: RefCell<FollowTrajectorySettings>,
     ) -> Self {
         Self {
             move_to_point_settings,
             turn_to_settings,
             boomerang_settings,
             ramsete_hybrid_settings,
+            pure_pursuit_settings,
+            follow_trajectory_settings,
         }
     }
 }
@@ -62,6 +73,11 @@ pub struct Chassis<T: Tracking> {
     pub(super) motion_handler: MotionHandler,
     pub(super) motion_settings: MotionSettings,
     pub(super) distance_traveled: RefCell<Option<f64>>,
+    pub(super) curvature_drive_state: RefCell<CurvatureDriveState>,
+    pub(super) heading_hold: RefCell<Option<HeadingHoldSettings>>,
+    pub(super) driver_slew_params: RefCell<Option<DriverSlewParameters>>,
+    pub(super) traction_control_params: RefCell<Option<TractionControlParameters>>,
+    pub(super) driver_output_state: RefCell<DriverOutputState>,
 }
 
 impl<T: Tracking> Chassis<T> {
@@ -80,6 +96,11 @@ impl<T: Tracking> Chassis<T> {
             motion_handler: MotionHandler::new(),
             distance_traveled: RefCell::new(None),
             motion_settings,
+            curvature_drive_state: RefCell::new(CurvatureDriveState::default()),
+            heading_hold: RefCell::new(None),
+            driver_slew_params: RefCell::new(None),
+            traction_control_params: RefCell::new(None),
+            driver_output_state: RefCell::new(DriverOutputState::default()),
         })
     }
 
@@ -106,13 +127,36 @@ impl<T: Tracking> Chassis<T> {
             }
         }
     }
+    /// Waits until no motion is running or queued. This waits for the whole queue
+    /// rather than only the running motion. To wait for one motion, await its
+    /// [`crate::differential::motions::MotionHandle`].
     pub async fn wait_until_complete(&self) {
-        loop {
-            vexide::time::sleep(Duration::from_millis(5)).await;
-            if self.distance_traveled.borrow().is_none() {
-                break;
-            }
-        }
+        self.motion_handler.wait_until_idle().await;
+    }
+
+    pub fn is_in_motion(&self) -> bool {
+        self.motion_handler.is_in_motion()
+    }
+
+    /// The number of motions waiting behind the running one.
+    pub fn queued_motions(&self) -> usize {
+        self.motion_handler.queued_motions()
+    }
+
+    /// Removes the motions that have not started yet.
+    pub fn clear_motion_queue(&self) {
+        self.motion_handler.clear_queue();
+    }
+
+    /// Cancels the running motion and waits for it to end. The queued motions
+    /// still run after it.
+    pub async fn cancel_motion(&self) {
+        self.motion_handler.cancel_motion().await;
+    }
+
+    /// Cancels the running motion and all the queued motions.
+    pub async fn cancel_all_motions(&self) {
+        self.motion_handler.cancel_all_motions().await;
     }
 
     pub async fn set_pose(&self, pose: impl Into<Pose>) {
@@ -126,27 +170,23 @@ impl<T: Tracking> Chassis<T> {
         let mut tracking_lock = self.tracking.lock().await;
         Pose::from(tracking_lock.position())
     }
+    /// Drives with throttle and steer inputs, where positive steer turns clockwise.
+    ///
+    /// If heading hold is enabled with [`Chassis::set_heading_hold`], steer inputs in
+    /// its dead zone hold the heading instead.
     pub fn arcade(&self, mut throttle: f64, mut steer: f64, use_drive_curve: bool) {
         if use_drive_curve {
             throttle = self.throttle_curve.update(throttle);
+        }
+        if let Some(heading_hold_steer) = self.heading_hold_steer(steer) {
+            steer = heading_hold_steer;
+        } else if use_drive_curve {
             steer = self.steer_curve.update(steer);
         }
-        self.drivetrain
-            .left_motors
-            .borrow_mut()
-            .set_voltage_all_for_types(
-                (throttle + steer) * Motor::V5_MAX_VOLTAGE,
-                (throttle + steer) * Motor::EXP_MAX_VOLTAGE,
-            );
-        self.drivetrain
-            .right_motors
-            .borrow_mut()
-            .set_voltage_all_for_types(
-                (throttle - steer) * Motor::V5_MAX_VOLTAGE,
-                (throttle - steer) * Motor::EXP_MAX_VOLTAGE,
-            );
+        self.drive_sides(throttle + steer, throttle - steer);
     }
 
+    /// Drives each side with its own input.
     pub fn tank(&self, mut left: f64, mut right: f64, use_drive_curve: bool) {
         left = if use_drive_curve {
             self.throttle_curve.update(left)
@@ -158,16 +198,6 @@ impl<T: Tracking> Chassis<T> {
         } else {
             right
         };
-        self.drivetrain
-            .left_motors
-            .borrow_mut()
-            .set_voltage_all_for_types(left * Motor::V5_MAX_VOLTAGE, left * Motor::EXP_MAX_VOLTAGE);
-        self.drivetrain
-            .right_motors
-            .borrow_mut()
-            .set_voltage_all_for_types(
-                right * Motor::V5_MAX_VOLTAGE,
-                right * Motor::EXP_MAX_VOLTAGE,
-            );
+        self.drive_sides(left, right);
     }
 }
@@ -0,0 +1,164 @@
+use alloc::vec::Vec;
+
+use num_traits::float::Float;
+
+use super::DriveCurve;
+
+/// A drive curve through hand-placed control points, interpolated with a monotone
+/// cubic spline (Fritsch–Carlson) so that it never overshoots between points.
+///
+/// The control points cover inputs from 0 to 1, and negative inputs mirror them
+/// (odd symmetry). Inputs below the first control point are a dead zone and
+/// inputs above the last control point hold its output.
+///
+/// # Example
+///
+/// ```
+/// let throttle_curve = LookupTableDriveCurve::new(vec![
+///     (0.05, 0.0),
+///     (0.5, 0.3),
+///     (0.8, 0.6),
+///     (1.0, 1.0),
+/// ]);
+/// ```
+#[derive(Clone)]
+pub struct LookupTableDriveCurve {
+    /// Pairs of input and output, sorted by input.
+    points: Vec<(f64, f64)>,
+
+    /// The slope of the spline at each control point.
+    tangents: Vec<f64>,
+}
+
+impl LookupTableDriveCurve {
+    /// # Panics
+    ///
+    /// Panics if there are fewer than two points, if an input is outside of `[0, 1]`
+    /// or repeated, if an output is negative, if the outputs are not non-decreasing
+    /// in the input, or if a point at an input of 0 has a nonzero output.
+    pub fn new(mut points: Vec<(f64, f64)>) -> Self {
+        assert!(
+            points.len() >= 2,
+            "A lookup table requires at least two points."
+        );
+        points.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+        for &(input, output) in points.iter() {
+            assert!(
+                (0.0..=1.0).contains(&input),
+                "Control point inputs must be within [0, 1]."
+            );
+            assert!(output >= 0.0, "Control point outputs may not be negative.");
+        }
+        for window in points.windows(2) {
+            assert!(
+                window[0].0 < window[1].0,
+                "Control point inputs may not repeat."
+            );
+            assert!(
+                window[0].1 <= window[1].1,
+                "Control point outputs must be monotonically non-decreasing."
+            );
+        }
+        assert!(
+            points[0].0 != 0.0 || points[0].1 == 0.0,
+            "An input of 0 must have an output of 0 to keep odd symmetry."
+        );
+
+        let tangents = Self::fritsch_carlson_tangents(&points);
+        Self { points, tangents }
+    }
+
+    /// Samples any curve at evenly spaced inputs from 0 to 1, such as to compare
+    /// curves or to use one as the starting point of a hand-tuned table.
+    ///
+    /// The sample at an input of 0 always has an output of 0. Curves without a dead
+    /// zone may jump straight to a minimum output, such as an
+    /// [`ExponentialDriveCurve`](super::exponential::ExponentialDriveCurve) with a
+    /// dead zone of 0, and that jump is spread over the first sample interval.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than two samples, or if the sampled outputs break
+    /// the requirements of [`Self::new`], such as for a curve that is negative or
+    /// decreasing on `[0, 1]`.
+    pub fn from_curve(curve: &dyn DriveCurve, samples: usize) -> Self {
+        assert!(samples >= 2, "At least two samples are required.");
+        Self::new(
+            (0..samples)
+                .map(|i| {
+                    let input = i as f64 / (samples - 1) as f64;
+                    let output = if i == 0 { 0.0 } else { curve.update(input) };
+                    (input, output)
+                })
+                .collect(),
+        )
+    }
+
+    /// The control points, sorted by input.
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+
+    fn fritsch_carlson_tangents(points: &[(f64, f64)]) -> Vec<f64> {
+        let secants = points
+            .windows(2)
+            .map(|window| (window[1].1 - window[0].1) / (window[1].0 - window[0].0))
+            .collect::<Vec<_>>();
+
+        let mut tangents = Vec::with_capacity(points.len());
+        tangents.push(secants[0]);
+        for window in secants.windows(2) {
+            tangents.push(if window[0] * window[1] <= 0.0 {
+                0.0
+            } else {
+                (window[0] + window[1]) / 2.0
+            });
+        }
+        tangents.push(secants[secants.len() - 1]);
+
+        // Limit the tangents so that each segment stays monotone.
+        for (i, &secant) in secants.iter().enumerate() {
+            if secant == 0.0 {
+                tangents[i] = 0.0;
+                tangents[i + 1] = 0.0;
+                continue;
+            }
+            let alpha = tangents[i] / secant;
+            let beta = tangents[i + 1] / secant;
+            let magnitude = alpha.hypot(beta);
+            if magnitude > 3.0 {
+                tangents[i] = 3.0 * alpha * secant / magnitude;
+                tangents[i + 1] = 3.0 * beta * secant / magnitude;
+            }
+        }
+        tangents
+    }
+}
+
+impl DriveCurve for LookupTableDriveCurve {
+    fn update(&self, input: f64) -> f64 {
+        let magnitude = input.abs().min(1.0);
+        let (first_input, _) = self.points[0];
+        let (last_input, last_output) = self.points[self.points.len() - 1];
+        if magnitude < first_input {
+            return 0.0;
+        }
+        if magnitude >= last_input {
+            return last_output * input.signum();
+        }
+
+        // Evaluate the cubic Hermite segment containing the input.
+        let i = self.points.partition_point(|(x, _)| *x <= magnitude) - 1;
+        let (x0, y0) = self.points[i];
+        let (x1, y1) = self.points[i + 1];
+        let h = x1 - x0;
+        let t = (magnitude - x0) / h;
+        let t2 = t * t;
+        let t3 = t2 * t;
+        let output = (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+            + (t3 - 2.0 * t2 + t) * h * self.tangents[i]
+            + (-2.0 * t3 + 3.0 * t2) * y1
+            + (t3 - t2) * h * self.tangents[i + 1];
+        output * input.signum()
+    }
+}
@@ -1,5 +1,6 @@
 pub mod exponential;
 pub mod flipped_power;
+pub mod lookup_table;
 pub mod power;
 
 dyn_clone::clone_trait_object!(DriveCurve);
@@ -0,0 +1,401 @@
+use alloc::{boxed::Box, rc::Rc, vec::Vec};
+use core::{f64::consts::PI, time::Duration};
+
+use bon::Builder;
+use vexide::prelude::{Float, Motor};
+
+use super::{chassis::Chassis, pose::Pose};
+use crate::{
+    controllers::FeedbackController,
+    devices::motor_group::MotorGroup,
+    tracking::Tracking,
+    utils::{
+        clock::{vex_clock, Clock},
+        math::{angle_error, arcade_desaturate, delta_clamp},
+    },
+};
+
+#[derive(Clone, Copy, PartialEq, Builder)]
+pub struct CurvatureDriveParameters {
+    /// Scales how sharply the steer input curves the path.
+    #[builder(default = 1.0)]
+    pub sensitivity: f64,
+
+    /// Scales the negative inertia term, which adds to sudden steer changes to
+    /// counter the robot's rotational inertia. Zero disables it.
+    #[builder(default = 0.0)]
+    pub negative_inertia_scalar: f64,
+
+    /// Below this throttle, quick turn builds up the quick stop accumulator, which
+    /// counters the rotation left over when quick turn is released.
+    #[builder(default = 0.2)]
+    pub quick_stop_threshold: f64,
+
+    /// How quickly the quick stop accumulator follows the steer input, from 0 to 1.
+    #[builder(default = 0.1)]
+    pub quick_stop_alpha: f64,
+
+    /// Scales the steer input the quick stop accumulator follows.
+    #[builder(default = 2.0)]
+    pub quick_stop_scalar: f64,
+}
+
+#[macro_export]
+macro_rules! params_curvature {
+    (
+        $($key:ident : $value:expr),* $(,)?
+    ) => {
+        $crate::differential::driver::CurvatureDriveParameters::builder()
+            $(.$key($value))*
+            .build()
+    };
+}
+pub use params_curvature;
+
+/// State carried between calls of [`Chassis::curvature`].
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct CurvatureDriveState {
+    previous_steer: f64,
+    negative_inertia_accumulator: f64,
+    quick_stop_accumulator: f64,
+}
+
+/// Limits how quickly the output of each drive side changes under driver control,
+/// in full power per second. A limit of zero disables it.
+///
+/// Moving away from zero is limited by the acceleration and moving towards or through
+/// zero by the deceleration, so a lower deceleration keeps a loaded robot from tipping
+/// when the stick is released.
+#[derive(Clone, Copy, PartialEq, Builder)]
+pub struct DriverSlewParameters {
+    #[builder(default = 0.0)]
+    pub max_acceleration: f64,
+
+    #[builder(default = 0.0)]
+    pub max_deceleration: f64,
+}
+
+#[macro_export]
+macro_rules! params_driver_slew {
+    (
+        $($key:ident : $value:expr),* $(,)?
+    ) => {
+        $crate::differential::driver::DriverSlewParameters::builder()
+            $(.$key($value))*
+            .build()
+    };
+}
+pub use params_driver_slew;
+
+/// Detects wheel slip under driver control by comparing the speed of the drive wheels,
+/// from the motors, with the speed of the ground under each side, from tracking.
+#[derive(Clone, Copy, PartialEq, Builder)]
+pub struct TractionControlParameters {
+    /// Diameter of the drive wheels in inches.
+    pub wheel_diameter: f64,
+
+    /// The RPM of the drive wheels when the motors spin at the maximum RPM of their gearset.
+    pub drive_wheel_rpm: f64,
+
+    /// Distance between the left and right drive wheels in inches.
+    pub track_width: f64,
+
+    /// How much faster the wheels of a side may spin than the ground moves under
+    /// them, in inches per second, before the side is slipping.
+    #[builder(default = 6.0)]
+    pub slip_threshold: f64,
+
+    /// Scales the output of a slipping side.
+    #[builder(default = 0.5)]
+    pub slip_output_scalar: f64,
+}
+
+#[macro_export]
+macro_rules! params_traction_control {
+    (
+        $($key:ident : $value:expr),* $(,)?
+    ) => {
+        $crate::differential::driver::TractionControlParameters::builder()
+            $(.$key($value))*
+            .build()
+    };
+}
+pub use params_traction_control;
+
+/// Updates further apart than this, such as after an autonomous motion, restart
+/// the slew limits and traction control as if they were the first update.
+const MAX_UPDATE_GAP: Duration = Duration::from_millis(100);
+
+/// State carried between driver control updates for the slew limits and traction control.
+pub struct DriverOutputState {
+    previous_output: (f64, f64),
+    previous_time: Option<Duration>,
+    previous_pose: Option<Pose>,
+    clock: Rc<dyn Clock>,
+}
+
+impl Default for DriverOutputState {
+    fn default() -> Self {
+        Self {
+            previous_output: (0.0, 0.0),
+            previous_time: None,
+            previous_pose: None,
+            clock: vex_clock(),
+        }
+    }
+}
+
+/// Keeps the heading while the driver is not steering, to counter drift from
+/// uneven drive sides.
+///
+/// The controller is given the heading error in radians, counterclockwise positive,
+/// and its output replaces the steer input.
+#[derive(Clone)]
+pub struct HeadingHoldSettings {
+    controller: Box<dyn FeedbackController<f64>>,
+
+    /// Steer inputs with a magnitude up to this lock the heading.
+    dead_zone: f64,
+    held_heading: Option<f64>,
+}
+
+impl HeadingHoldSettings {
+    pub fn new(controller: Box<dyn FeedbackController<f64>>, dead_zone: f64) -> Self {
+        Self {
+            controller,
+            dead_zone: dead_zone.abs(),
+            held_heading: None,
+        }
+    }
+
+    pub fn held_heading(&self) -> Option<f64> {
+        self.held_heading
+    }
+
+    pub fn release(&mut self) {
+        self.held_heading = None;
+        self.controller.reset();
+    }
+}
+
+/// Moves an accumulator towards zero by up to one.
+fn decay_accumulator(accumulator: &mut f64) {
+    *accumulator = if *accumulator > 1.0 {
+        *accumulator - 1.0
+    } else if *accumulator < -1.0 {
+        *accumulator + 1.0
+    } else {
+        0.0
+    };
+}
+
+fn slew_side(
+    target: f64,
+    current: f64,
+    params: &DriverSlewParameters,
+    delta_time: Duration,
+) -> f64 {
+    let max_delta = if target.abs() > current.abs() && target * current >= 0.0 {
+        params.max_acceleration
+    } else {
+        params.max_deceleration
+    };
+    delta_clamp(target, current, max_delta, Some(delta_time))
+}
+
+/// The average speed of the wheels driven by `motors` in inches per second,
+/// or `None` if no motor could be read.
+fn wheel_speed(motors: &MotorGroup, params: &TractionControlParameters) -> Option<f64> {
+    let wheel_rpms: Vec<f64> = motors
+        .velocity_all()
+        .into_iter()
+        .zip(motors.gearset_all())
+        .filter_map(|(velocity, gearset)| {
+            Some(velocity.ok()? * params.drive_wheel_rpm / gearset.ok()?.max_rpm())
+        })
+        .collect();
+    if wheel_rpms.is_empty() {
+        return None;
+    }
+    let wheel_rpm = wheel_rpms.iter().sum::<f64>() / wheel_rpms.len() as f64;
+    Some(wheel_rpm / 60.0 * PI * params.wheel_diameter)
+}
+
+/// Whether the wheels of a side spin faster than the ground moves under them.
+fn is_slipping(
+    wheel_speed: Option<f64>,
+    ground_speed: f64,
+    params: &TractionControlParameters,
+) -> bool {
+    wheel_speed.is_some_and(|wheel_speed| {
+        wheel_speed.abs() > ground_speed.abs()
+            && (wheel_speed - ground_speed).abs() > params.slip_threshold
+    })
+}
+
+impl<T: Tracking> Chassis<T> {
+    /// Limits how quickly driver control changes the output of each side,
+    /// or removes the limits with `None`.
+    pub fn set_driver_slew(&self, params: Option<DriverSlewParameters>) {
+        *self.driver_slew_params.borrow_mut() = params;
+    }
+
+    /// Enables traction control under driver control, or disables it with `None`.
+    pub fn set_traction_control(&self, params: Option<TractionControlParameters>) {
+        *self.traction_control_params.borrow_mut() = params;
+    }
+
+    /// Drives each side with an output out of 1, after the driver slew limits
+    /// and traction control.
+    pub(super) fn drive_sides(&self, mut left: f64, mut right: f64) {
+        let mut state = self.driver_output_state.borrow_mut();
+        let now = state.clock.now();
+        let delta_time = state
+            .previous_time
+            .map(|previous_time| now.saturating_sub(previous_time))
+            .filter(|delta_time| *delta_time <= MAX_UPDATE_GAP);
+        state.previous_time = Some(now);
+        if delta_time.is_none() {
+            state.previous_pose = None;
+        }
+        let delta_time = delta_time.unwrap_or(Motor::WRITE_INTERVAL);
+
+        left = left.clamp(-1.0, 1.0);
+        right = right.clamp(-1.0, 1.0);
+        if let Some(params) = *self.driver_slew_params.borrow() {
+            let (previous_left, previous_right) = state.previous_output;
+            left = slew_side(left, previous_left, &params, delta_time);
+            right = slew_side(right, previous_right, &params, delta_time);
+        }
+
+        // Skip a cycle rather than block if tracking is busy.
+        let traction_control_params = *self.traction_control_params.borrow();
+        let pose = traction_control_params.and_then(|_| {
+            self.tracking
+                .try_lock()
+                .map(|mut tracking| Pose::from(tracking.position()))
+        });
+        if let (Some(params), Some(pose), Some(previous_pose)) =
+            (traction_control_params, pose, state.previous_pose)
+        {
+            let delta_seconds = delta_time.as_secs_f64();
+            if delta_seconds > 0.0 {
+                let delta_position = pose.position - previous_pose.position;
+                let linear_velocity = (delta_position.x * pose.orientation.cos()
+                    + delta_position.y * pose.orientation.sin())
+                    / delta_seconds;
+                let angular_velocity =
+                    angle_error(pose.orientation, previous_pose.orientation, true, None)
+                        / delta_seconds;
+                let half_track_width = params.track_width / 2.0;
+
+                let left_wheel_speed = wheel_speed(&self.drivetrain.left_motors.borrow(), &params);
+                let right_wheel_speed =
+                    wheel_speed(&self.drivetrain.right_motors.borrow(), &params);
+                if is_slipping(
+                    left_wheel_speed,
+                    linear_velocity - angular_velocity * half_track_width,
+                    &params,
+                ) {
+                    left *= params.slip_output_scalar;
+                }
+                if is_slipping(
+                    right_wheel_speed,
+                    linear_velocity + angular_velocity * half_track_width,
+                    &params,
+                ) {
+                    right *= params.slip_output_scalar;
+                }
+            }
+        }
+        state.previous_pose = pose;
+        state.previous_output = (left, right);
+
+        self.drivetrain
+            .left_motors
+            .borrow_mut()
+            .set_voltage_all_for_types(left * Motor::V5_MAX_VOLTAGE, left * Motor::EXP_MAX_VOLTAGE);
+        self.drivetrain
+            .right_motors
+            .borrow_mut()
+            .set_voltage_all_for_types(
+                right * Motor::V5_MAX_VOLTAGE,
+                right * Motor::EXP_MAX_VOLTAGE,
+            );
+    }
+
+    /// Enables heading hold for [`Chassis::arcade`], or disables it with `None`.
+    pub fn set_heading_hold(&self, settings: Option<HeadingHoldSettings>) {
+        *self.heading_hold.borrow_mut() = settings;
+    }
+
+    /// Releases the held heading, if any. Heading hold locks again the next time
+    /// the steer input is inside its dead zone.
+    pub fn release_heading_hold(&self) {
+        if let Some(heading_hold) = self.heading_hold.borrow_mut().as_mut() {
+            heading_hold.release();
+        }
+    }
+
+    /// The steer that holds the heading, clockwise positive like the steer input,
+    /// or `None` if heading hold is disabled, released or the pose is unavailable.
+    pub(super) fn heading_hold_steer(&self, steer: f64) -> Option<f64> {
+        let mut heading_hold = self.heading_hold.borrow_mut();
+        let heading_hold = heading_hold.as_mut()?;
+        if steer.abs() > heading_hold.dead_zone || self.motion_handler.is_in_motion() {
+            heading_hold.release();
+            return None;
+        }
+
+        // Skip a cycle rather than block if tracking is busy.
+        let heading = Pose::from(self.tracking.try_lock()?.position()).orientation;
+        let held_heading = *heading_hold.held_heading.get_or_insert(heading);
+        let error = angle_error(held_heading, heading, true, None);
+        Some(-heading_hold.controller.update(error, 0.0))
+    }
+
+    /// Curvature (cheesy) drive, where the steer input sets the curvature of the path
+    /// instead of the turn rate, so turns keep the same shape at any speed.
+    ///
+    /// While `quick_turn` is held, the steer input sets the turn rate directly to
+    /// turn in place. As with [`Chassis::arcade`], positive steer turns clockwise.
+    pub fn curvature(
+        &self,
+        mut throttle: f64,
+        mut steer: f64,
+        quick_turn: bool,
+        use_drive_curve: bool,
+        params: Option<CurvatureDriveParameters>,
+    ) {
+        let params = params.unwrap_or(params_curvature!());
+        if use_drive_curve {
+            throttle = self.throttle_curve.update(throttle);
+            steer = self.steer_curve.update(steer);
+        }
+        let mut state = self.curvature_drive_state.borrow_mut();
+
+        let negative_inertia = steer - state.previous_steer;
+        state.previous_steer = steer;
+        state.negative_inertia_accumulator += negative_inertia * params.negative_inertia_scalar;
+        steer += state.negative_inertia_accumulator;
+        decay_accumulator(&mut state.negative_inertia_accumulator);
+
+        let angular = if quick_turn {
+            if throttle.abs() < params.quick_stop_threshold {
+                state.quick_stop_accumulator = (1.0 - params.quick_stop_alpha)
+                    * state.quick_stop_accumulator
+                    + params.quick_stop_alpha * steer.clamp(-1.0, 1.0) * params.quick_stop_scalar;
+            }
+            steer
+        } else {
+            let angular =
+                throttle.abs() * steer * params.sensitivity - state.quick_stop_accumulator;
+            decay_accumulator(&mut state.quick_stop_accumulator);
+            angular
+        };
+
+        // Desaturation treats counterclockwise as positive.
+        let (left, right) = arcade_desaturate(throttle, -angular);
+        self.drive_sides(left, right);
+    }
+}
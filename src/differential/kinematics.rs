@@ -0,0 +1,28 @@
+/// Converts between the velocities of a differential drive as a whole and of its sides.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DifferentialDriveKinematics {
+    /// Distance between the left and right wheels.
+    pub track_width: f64,
+}
+
+impl DifferentialDriveKinematics {
+    pub fn new(track_width: f64) -> Self {
+        assert!(track_width > 0.0, "The track width must be positive.");
+        Self { track_width }
+    }
+
+    /// The left and right wheel velocities for a linear and angular velocity,
+    /// with the angular velocity in radians and counterclockwise positive.
+    pub fn to_wheel_velocities(&self, linear_velocity: f64, angular_velocity: f64) -> (f64, f64) {
+        let difference = angular_velocity * self.track_width / 2.0;
+        (linear_velocity - difference, linear_velocity + difference)
+    }
+
+    /// The linear and angular velocity for the left and right wheel velocities.
+    pub fn to_chassis_velocities(&self, left_velocity: f64, right_velocity: f64) -> (f64, f64) {
+        (
+            (left_velocity + right_velocity) / 2.0,
+            (right_velocity - left_velocity) / self.track_width,
+        )
+    }
+}
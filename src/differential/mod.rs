@@ -1,7 +1,13 @@
 pub mod chassis;
 pub mod drive_curve;
 #[macro_use]
+pub mod driver;
+pub mod kinematics;
+#[macro_use]
 pub mod motions;
+pub mod path;
+#[macro_use]
+pub mod trajectory;
 
 #[macro_use]
 pub mod pose {
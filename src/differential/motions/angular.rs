@@ -7,17 +7,17 @@ use core::{
 use bon::{bon, Builder};
 use nalgebra::Vector2;
 use num_traits::AsPrimitive;
-use vexide::prelude::{BrakeMode, Float, Motor, MotorControl};
+use vexide::prelude::{BrakeMode, Float};
 
-use super::ToleranceGroup;
+use super::{
+    log_telemetry, Motion, MotionExitReason, MotionHandle, MotionOutput, MotionPriority,
+    SettingsSource, SideOutput, ToleranceGroup,
+};
 use crate::{
     controllers::FeedbackController,
     differential::{chassis::Chassis, pose::Pose},
     tracking::Tracking,
-    utils::{
-        math::{angle_error, arcade_desaturate, delta_clamp, AngularDirection},
-        timer::Timer,
-    },
+    utils::math::{angle_error, arcade_desaturate, delta_clamp, AngularDirection},
 };
 
 #[derive(Clone, Copy, PartialEq)]
@@ -146,185 +146,184 @@ impl<T: AsPrimitive<f64>, U: AsPrimitive<f64>> From<(T, U)> for TurnToTarget {
     }
 }
 
-#[bon]
-impl<T: Tracking + 'static> Chassis<T> {
-    #[builder]
-    pub async fn turn_to(
-        self: Rc<Self>,
-        target: impl Into<TurnToTarget> + 'static,
-        timeout: Option<Duration>,
-        params: Option<TurnToParameters>,
-        mut settings: Option<TurnToSettings>,
-        run_async: Option<bool>,
-    ) {
-        let unwrapped_params = params.unwrap_or(params_turn_to!());
-        self.motion_handler.wait_for_motions_end().await;
-        if !self.motion_handler.is_in_motion() {
-            return;
+/// Turns in place to face a target, or about one side with a swing turn.
+pub struct TurnToMotion {
+    target: TurnToTarget,
+    params: TurnToParameters,
+    settings: SettingsSource<TurnToSettings>,
+    previous_raw_error: Option<f64>,
+    oscillations_begin: bool,
+    previous_output: f64,
+    angular_error: Option<f64>,
+    exit_reason: Option<MotionExitReason>,
+}
+
+impl TurnToMotion {
+    pub fn new(
+        target: impl Into<TurnToTarget>,
+        params: TurnToParameters,
+        settings: impl Into<SettingsSource<TurnToSettings>>,
+    ) -> Self {
+        Self {
+            target: target.into(),
+            params,
+            settings: settings.into(),
+            previous_raw_error: None,
+            oscillations_begin: false,
+            previous_output: 0.0,
+            angular_error: None,
+            exit_reason: None,
         }
-        if run_async.unwrap_or(true) {
-            // Spawn vexide task
-            vexide::task::spawn({
-                let self_clone = self.clone();
-                async move {
-                    self_clone
-                        .turn_to()
-                        .target(target)
-                        .maybe_timeout(timeout)
-                        .maybe_params(params)
-                        .maybe_settings(settings)
-                        .run_async(false)
-                        .call()
-                        .await
-                }
+    }
+}
+
+impl Motion for TurnToMotion {
+    fn init(&mut self, _pose: Pose) {
+        self.settings.resolve().reset();
+    }
+
+    fn step(&mut self, pose: Pose) -> MotionOutput {
+        let settings = self.settings.resolve();
+        let facing_pose = pose + Pose::new(0.0, 0.0, if self.params.forwards { 0.0 } else { PI });
+        let raw_error = self.target.error(facing_pose, None);
+
+        // If it crosses error being 0, then drop the `direction` parameter.
+        if !self.oscillations_begin
+            && self.previous_raw_error.is_some_and(|previous_raw_error| {
+                previous_raw_error.signum() != raw_error.signum()
+                    // Make sure the crossing is actually on low error:
+                    && (0.0..FRAC_PI_2).contains(&previous_raw_error.abs())
             })
-            .detach();
-            self.motion_handler.end_motion().await;
-            vexide::time::sleep(Duration::from_millis(10)).await;
-            return;
+            && (0.0..FRAC_PI_2).contains(&raw_error.abs())
+        {
+            self.oscillations_begin = true;
         }
-        if let Some(settings) = &mut settings {
-            settings.reset();
+
+        self.previous_raw_error = Some(raw_error);
+
+        let error = if self.oscillations_begin {
+            raw_error
         } else {
-            self.motion_settings.turn_to_settings.borrow_mut().reset();
+            self.target.error(facing_pose, self.params.direction)
+        };
+        self.angular_error = Some(error);
+        if self.params.min_speed != 0.0 && self.oscillations_begin
+            || raw_error.abs() < self.params.early_exit_range
+        {
+            self.exit_reason = Some(MotionExitReason::EarlyExit);
+            return MotionOutput::velocity(0.0, 0.0);
         }
-        *self.distance_traveled.borrow_mut() = Some(0.0);
-        match unwrapped_params.locked_side {
+        if settings.angular_tolerances.update_all(error) {
+            self.exit_reason = Some(MotionExitReason::Settled);
+            return MotionOutput::velocity(0.0, 0.0);
+        }
+
+        // Apply controllers.
+        let mut raw_output = if self.params.locked_side.is_some() {
+            settings.swing_controller.update(error, 0.0)
+        } else {
+            settings.angular_controller.update(error, 0.0)
+        }
+        .clamp(-self.params.max_speed, self.params.max_speed);
+        raw_output = delta_clamp(
+            raw_output,
+            self.previous_output,
+            self.params.angular_slew.unwrap_or(0.0),
+            None,
+        );
+        if (-self.params.min_speed..0.0).contains(&raw_output) {
+            raw_output = -self.params.min_speed;
+        } else if (0.0..self.params.min_speed).contains(&raw_output) {
+            raw_output = self.params.min_speed;
+        }
+        self.previous_output = raw_output;
+        let (left, right) = arcade_desaturate(
+            match self.params.locked_side {
+                Some(DifferentialDriveSide::Left) => raw_output,
+                Some(DifferentialDriveSide::Right) => -raw_output,
+                None => 0.0,
+            },
+            raw_output,
+        );
+        log_telemetry(
+            "turn_to",
+            &[if self.params.locked_side.is_some() {
+                ("swing", settings.swing_controller.as_ref())
+            } else {
+                ("angular", settings.angular_controller.as_ref())
+            }],
+        );
+        let mut output = MotionOutput::velocity(left, right);
+        match self.params.locked_side {
             Some(DifferentialDriveSide::Left) => {
-                self.drivetrain
-                    .left_motors
-                    .borrow_mut()
-                    .set_target_all(MotorControl::Brake(BrakeMode::Brake));
+                output.left = SideOutput::Brake(BrakeMode::Brake);
             }
             Some(DifferentialDriveSide::Right) => {
-                self.drivetrain
-                    .right_motors
-                    .borrow_mut()
-                    .set_target_all(MotorControl::Brake(BrakeMode::Brake));
+                output.right = SideOutput::Brake(BrakeMode::Brake);
             }
             None => {}
-        };
-        let target: TurnToTarget = target.into();
-        let mut previous_pose = self.pose().await;
-        let mut previous_raw_error: Option<f64> = None;
-        let mut oscillations_begin = false;
-        let mut previous_output: f64 = 0.0;
-        let mut timer = Timer::new(timeout.unwrap_or(Duration::MAX));
-
-        while !timer.is_done() && self.motion_handler.is_in_motion() {
-            let pose = self.pose().await;
-            if let Some(distance) = self.distance_traveled.borrow_mut().as_mut() {
-                *distance +=
-                    (angle_error(pose.orientation, previous_pose.orientation, true, None)).abs();
-            }
-            previous_pose = pose;
-
-            let raw_error = target.error(
-                pose + Pose::new(0.0, 0.0, if unwrapped_params.forwards { 0.0 } else { PI }),
-                None,
-            );
-
-            // If it crosses error being 0, then drop the `direction` parameter.
-            if !oscillations_begin
-                && previous_raw_error.unwrap().signum() != raw_error.signum()
-                // Make sure the crossing is actually on low error:
-                && (0.0..FRAC_PI_2).contains(&previous_raw_error.unwrap_or(raw_error).abs())
-                && (0.0..FRAC_PI_2).contains(&raw_error.abs())
-            {
-                oscillations_begin = true;
-            }
+        }
+        output
+    }
 
-            previous_raw_error = Some(raw_error);
+    fn is_finished(&self) -> bool {
+        self.exit_reason.is_some()
+    }
 
-            if unwrapped_params.min_speed != 0.0 && oscillations_begin
-                || raw_error.abs() < unwrapped_params.early_exit_range
-            {
-                break;
-            }
-            let error = if oscillations_begin {
-                raw_error
-            } else {
-                target.error(
-                    pose + Pose::new(0.0, 0.0, if unwrapped_params.forwards { 0.0 } else { PI }),
-                    unwrapped_params.direction,
-                )
-            };
-            if if let Some(settings) = &mut settings {
-                settings.angular_tolerances.update_all(error)
-            } else {
-                self.motion_settings
-                    .boomerang_settings
-                    .borrow_mut()
-                    .angular_tolerances
-                    .update_all(error)
-            } {
-                break;
-            }
+    fn exit_reason(&self) -> MotionExitReason {
+        self.exit_reason.unwrap_or(MotionExitReason::Settled)
+    }
 
-            // Apply controllers.
-            let mut raw_output = if unwrapped_params.locked_side.is_some() {
-                if let Some(settings) = &mut settings {
-                    settings.swing_controller.update(error, 0.0)
-                } else {
-                    self.motion_settings
-                        .turn_to_settings
-                        .borrow_mut()
-                        .swing_controller
-                        .update(error, 0.0)
-                }
-            } else if let Some(settings) = &mut settings {
-                settings.angular_controller.update(error, 0.0)
-            } else {
-                self.motion_settings
-                    .turn_to_settings
-                    .borrow_mut()
-                    .angular_controller
-                    .update(error, 0.0)
-            }
-            .clamp(-unwrapped_params.max_speed, unwrapped_params.max_speed);
-            raw_output = delta_clamp(
-                raw_output,
-                previous_output,
-                unwrapped_params.angular_slew.unwrap_or(0.0),
-                None,
-            );
-            if (-unwrapped_params.min_speed..0.0).contains(&raw_output) {
-                raw_output = -unwrapped_params.min_speed;
-            } else if (0.0..unwrapped_params.min_speed).contains(&raw_output) {
-                raw_output = unwrapped_params.min_speed;
-            }
-            previous_output = raw_output;
-            let (left, right) = arcade_desaturate(
-                if let Some(locked_side) = unwrapped_params.locked_side {
-                    match locked_side {
-                        DifferentialDriveSide::Left => raw_output,
-                        DifferentialDriveSide::Right => -raw_output,
-                    }
-                } else {
-                    0.0
-                },
-                raw_output,
-            );
-            self.drivetrain
-                .left_motors
-                .borrow_mut()
-                .set_velocity_percentage_all(left);
-            self.drivetrain
-                .right_motors
-                .borrow_mut()
-                .set_velocity_percentage_all(right);
-
-            vexide::time::sleep(Motor::WRITE_INTERVAL).await;
-        }
-        self.drivetrain
-            .left_motors
-            .borrow_mut()
-            .set_target_all(vexide::prelude::MotorControl::Brake(BrakeMode::Coast));
-        self.drivetrain
-            .right_motors
-            .borrow_mut()
-            .set_target_all(vexide::prelude::MotorControl::Brake(BrakeMode::Coast));
-        *self.distance_traveled.borrow_mut() = None;
-        self.motion_handler.end_motion().await;
+    fn on_exit(&mut self) -> Option<MotionOutput> {
+        Some(MotionOutput::brake(BrakeMode::Coast))
+    }
+
+    fn angular_error(&self) -> Option<f64> {
+        self.angular_error
+    }
+
+    fn distance_between(&self, previous_pose: Pose, pose: Pose) -> f64 {
+        angle_error(pose.orientation, previous_pose.orientation, true, None).abs()
+    }
+
+    fn exit_velocity(&self) -> (f64, f64) {
+        (0.0, self.previous_output)
+    }
+
+    fn seed_velocity(&mut self, _linear: f64, angular: f64) {
+        self.previous_output = angular;
+    }
+
+    /// Turns exit once they cross their target at the minimum speed, so the exit
+    /// range is unused.
+    fn pass_through(&mut self, min_speed: f64, _exit_range: f64) {
+        self.params.min_speed = self.params.min_speed.max(min_speed);
+    }
+}
+
+#[bon]
+impl<T: Tracking + 'static> Chassis<T> {
+    #[builder]
+    pub async fn turn_to(
+        self: Rc<Self>,
+        target: impl Into<TurnToTarget> + 'static,
+        timeout: Option<Duration>,
+        params: Option<TurnToParameters>,
+        settings: Option<TurnToSettings>,
+        priority: Option<MotionPriority>,
+        run_async: Option<bool>,
+    ) -> MotionHandle {
+        let motion = TurnToMotion::new(
+            target,
+            params.unwrap_or(params_turn_to!()),
+            self.settings_source(settings, |settings| &settings.turn_to_settings),
+        );
+        self.run_motion()
+            .motion(motion)
+            .maybe_timeout(timeout)
+            .maybe_priority(priority)
+            .maybe_run_async(run_async)
+            .call()
+            .await
     }
 }
@@ -0,0 +1,92 @@
+use alloc::rc::Rc;
+use core::time::Duration;
+
+use bon::bon;
+use nalgebra::Vector2;
+use vexide::prelude::{BrakeMode, Float, Motor, MotorControl};
+
+use crate::{
+    controllers::autotune::{RelayAutotuneParameters, RelayAutotuneResult, RelayAutotuner},
+    differential::{chassis::Chassis, motions::MotionPriority},
+    tracking::Tracking,
+    utils::{
+        math::{angle_error, arcade_desaturate},
+        timer::Timer,
+    },
+};
+
+#[bon]
+impl<T: Tracking + 'static> Chassis<T> {
+    /// Relay autotunes turning in place about the heading the robot starts at.
+    ///
+    /// The relay amplitude is an angular velocity percentage, so the suggested gains
+    /// are directly usable for the angular controllers of the motions.
+    #[builder]
+    pub async fn autotune_angular(
+        self: Rc<Self>,
+        params: RelayAutotuneParameters,
+        timeout: Option<Duration>,
+    ) -> Option<RelayAutotuneResult> {
+        self.relay_autotune(params, timeout, true).await
+    }
+
+    /// Relay autotunes driving forwards and backwards about the position the robot starts at.
+    ///
+    /// The relay amplitude is a linear velocity percentage, so the suggested gains
+    /// are directly usable for the linear controllers of the motions.
+    #[builder]
+    pub async fn autotune_linear(
+        self: Rc<Self>,
+        params: RelayAutotuneParameters,
+        timeout: Option<Duration>,
+    ) -> Option<RelayAutotuneResult> {
+        self.relay_autotune(params, timeout, false).await
+    }
+
+    async fn relay_autotune(
+        &self,
+        params: RelayAutotuneParameters,
+        timeout: Option<Duration>,
+        angular: bool,
+    ) -> Option<RelayAutotuneResult> {
+        let ticket = self.motion_handler.enqueue(MotionPriority::Normal);
+        if !self.motion_handler.wait_for_turn(&ticket).await {
+            return None;
+        }
+        self.release_heading_hold();
+        let start_pose = self.pose().await;
+        let heading = Vector2::new(start_pose.orientation.cos(), start_pose.orientation.sin());
+        let mut autotuner = RelayAutotuner::new(0.0, params);
+        let mut timer = Timer::new(timeout.unwrap_or(Duration::from_secs(15)));
+        while !timer.is_done() && !autotuner.is_done() && self.motion_handler.is_in_motion() {
+            let pose = self.pose().await;
+            let (left, right) = if angular {
+                let rotation = angle_error(pose.orientation, start_pose.orientation, true, None);
+                arcade_desaturate(0.0, autotuner.update(rotation))
+            } else {
+                let displacement = (pose.position - start_pose.position).dot(&heading);
+                arcade_desaturate(autotuner.update(displacement), 0.0)
+            };
+            self.drivetrain
+                .left_motors
+                .borrow_mut()
+                .set_velocity_percentage_all(left);
+            self.drivetrain
+                .right_motors
+                .borrow_mut()
+                .set_velocity_percentage_all(right);
+
+            vexide::time::sleep(Motor::WRITE_INTERVAL).await;
+        }
+        self.drivetrain
+            .left_motors
+            .borrow_mut()
+            .set_target_all(MotorControl::Brake(BrakeMode::Coast));
+        self.drivetrain
+            .right_motors
+            .borrow_mut()
+            .set_target_all(MotorControl::Brake(BrakeMode::Coast));
+        self.motion_handler.end_motion(ticket);
+        autotuner.result()
+    }
+}
@@ -5,15 +5,15 @@ use bon::{bon, Builder};
 use num_traits::AsPrimitive;
 use vexide::prelude::Float;
 
-use super::ToleranceGroup;
+use super::{
+    log_telemetry, Motion, MotionExitReason, MotionHandle, MotionOutput, MotionPriority,
+    SettingsSource, ToleranceGroup,
+};
 use crate::{
     controllers::FeedbackController,
     differential::{chassis::Chassis, pose::Pose},
     tracking::Tracking,
-    utils::{
-        math::{angle_error, arcade_desaturate, delta_clamp},
-        timer::Timer,
-    },
+    utils::math::{angle_error, arcade_desaturate, delta_clamp},
 };
 
 #[derive(Clone, Copy, PartialEq, Builder)]
@@ -108,231 +108,253 @@ impl<T: AsPrimitive<f64>, U: AsPrimitive<f64>, V: AsPrimitive<f64>> From<(T, U,
     }
 }
 
-#[bon]
-impl<T: Tracking + 'static> Chassis<T> {
-    #[builder]
-    pub async fn boomerang(
-        self: Rc<Self>,
-        target: impl Into<MoveToPoseTarget> + 'static,
-        timeout: Option<Duration>,
-        params: Option<BoomerangParameters>,
-        mut settings: Option<BoomerangSettings>,
-        run_async: Option<bool>,
-    ) {
-        let mut unwrapped_params = params.unwrap_or(params_boomerang!());
-        unwrapped_params.min_linear_speed = unwrapped_params.min_linear_speed.abs();
-        unwrapped_params.max_linear_speed = unwrapped_params.max_linear_speed.abs();
-        unwrapped_params.max_angular_speed = unwrapped_params.max_angular_speed.abs();
+/// Drives to a pose by following a carrot point that leads the target, so that the
+/// robot arrives facing the target orientation.
+pub struct BoomerangMotion {
+    target: Pose,
+    params: BoomerangParameters,
+    settings: SettingsSource<BoomerangSettings>,
+    is_near: bool,
+    previous_was_same_side: bool,
+    previous_linear_output: f64,
+    previous_angular_output: f64,
+    linear_error: Option<f64>,
+    angular_error: Option<f64>,
+    exit_reason: Option<MotionExitReason>,
+}
+
+impl BoomerangMotion {
+    /// # Panics
+    ///
+    /// Panics if the minimum linear speed exceeds the maximum.
+    pub fn new(
+        target: impl Into<MoveToPoseTarget>,
+        mut params: BoomerangParameters,
+        settings: impl Into<SettingsSource<BoomerangSettings>>,
+    ) -> Self {
+        params.min_linear_speed = params.min_linear_speed.abs();
+        params.max_linear_speed = params.max_linear_speed.abs();
+        params.max_angular_speed = params.max_angular_speed.abs();
         assert!(
-            unwrapped_params.max_angular_speed >= unwrapped_params.min_linear_speed,
+            params.max_linear_speed >= params.min_linear_speed,
             "Minimum speed may not exceed the maximum."
         );
-        self.motion_handler.wait_for_motions_end().await;
-        if !self.motion_handler.is_in_motion() {
-            return;
+        Self {
+            target: target.into().0,
+            params,
+            settings: settings.into(),
+            is_near: false,
+            previous_was_same_side: false,
+            previous_linear_output: 0.0,
+            previous_angular_output: 0.0,
+            linear_error: None,
+            angular_error: None,
+            exit_reason: None,
         }
-        if run_async.unwrap_or(true) {
-            // Spawn vexide task
-            vexide::task::spawn({
-                let self_clone = self.clone();
-                async move {
-                    self_clone
-                        .boomerang()
-                        .target(target)
-                        .maybe_timeout(timeout)
-                        .maybe_params(params)
-                        .maybe_settings(settings.clone())
-                        .run_async(false)
-                        .call()
-                        .await
-                }
-            })
-            .detach();
-            self.motion_handler.end_motion().await;
-            vexide::time::sleep(Duration::from_millis(10)).await;
-            return;
+    }
+}
+
+impl Motion for BoomerangMotion {
+    fn init(&mut self, _pose: Pose) {
+        self.settings.resolve().reset();
+    }
+
+    fn step(&mut self, pose: Pose) -> MotionOutput {
+        let settings = self.settings.resolve();
+        let target = self.target;
+        let distance_to_target = pose.distance_to(&target);
+        if distance_to_target < 7.5 && !self.is_near {
+            self.is_near = true;
+            self.params.max_linear_speed = self.previous_linear_output.max(0.5);
         }
-        if let Some(ref mut settings) = settings {
-            settings.reset();
+        let robot_vectors_normalized =
+            nalgebra::Vector2::<f64>::new(target.orientation.cos(), target.orientation.sin());
+        let carrot_point = if self.is_near {
+            target.position
         } else {
-            self.motion_settings.boomerang_settings.borrow_mut().reset();
-        }
-        let mut previous_pose = self.pose().await;
-
-        *self.distance_traveled.borrow_mut() = Some(0.0);
-        let target = target.into().0;
-        let mut is_near = false;
-        let mut previous_was_same_side = false;
-        let mut previous_linear_output: f64 = 0.0;
-        let mut previous_angular_output: f64 = 0.0;
-        let mut timer = Timer::new(timeout.unwrap_or(Duration::MAX));
-        while !timer.is_done() && self.motion_handler.is_in_motion() {
-            let pose = self.pose().await;
-            if let Some(distance) = self.distance_traveled.borrow_mut().as_mut() {
-                *distance += pose.distance_to(&previous_pose);
-            }
-            let distance_to_target = pose.distance_to(&target);
-            previous_pose = pose;
-            if distance_to_target < 7.5 && !is_near {
-                is_near = true;
-                unwrapped_params.max_linear_speed = previous_linear_output.max(0.5);
+            target.position - robot_vectors_normalized * self.params.lead * distance_to_target
+        };
+        let carrot_pose_angle =
+            (carrot_point.y - pose.position.y).atan2(carrot_point.x - pose.position.x);
+        let linear_error = {
+            // Find the cosine of the signed angle between.
+            let cos_angle_difference =
+                angle_error(pose.orientation, carrot_pose_angle, true, None).cos();
+            if self.is_near {
+                distance_to_target * cos_angle_difference
+            } else {
+                distance_to_target * cos_angle_difference.signum()
             }
-            let robot_vectors_normalized =
-                nalgebra::Vector2::<f64>::new(target.orientation.cos(), target.orientation.sin());
-            let carrot_point = if is_near {
-                target.position
+        };
+        let angular_error = {
+            let adjusted_orientation = if self.params.forwards {
+                pose.orientation
             } else {
-                target.position
-                    - robot_vectors_normalized * unwrapped_params.lead * distance_to_target
-            };
-            let carrot_pose_angle =
-                (carrot_point.y - pose.position.y).atan2(carrot_point.x - pose.position.x);
-            let linear_error = {
-                // Find the cosine of the signed angle between.
-                let cos_angle_difference =
-                    angle_error(pose.orientation, carrot_pose_angle, true, None).cos();
-                if is_near {
-                    distance_to_target * cos_angle_difference
-                } else {
-                    distance_to_target * cos_angle_difference.signum()
-                }
-            };
-            let angular_error = {
-                let adjusted_orientation = if unwrapped_params.forwards {
-                    pose.orientation
-                } else {
-                    pose.orientation + PI
-                };
-                // Counterclockwise is positive.
-                if is_near {
-                    angle_error(adjusted_orientation, target.orientation, true, None)
-                } else {
-                    angle_error(adjusted_orientation, carrot_pose_angle, true, None)
-                }
+                pose.orientation + PI
             };
-
-            if if let Some(settings) = &mut settings {
-                let linear_done = settings.linear_tolerances.update_all(linear_error);
-                let angular_done = settings.angular_tolerances.update_all(angular_error);
-                linear_done && angular_done
+            // Counterclockwise is positive.
+            if self.is_near {
+                angle_error(adjusted_orientation, target.orientation, true, None)
             } else {
-                let mut motion_settings = self.motion_settings.boomerang_settings.borrow_mut();
-                let linear_done = motion_settings.linear_tolerances.update_all(linear_error);
-                let angular_done = motion_settings.angular_tolerances.update_all(angular_error);
-                linear_done && angular_done
-            } && is_near
-            {
-                break;
+                angle_error(adjusted_orientation, carrot_pose_angle, true, None)
             }
+        };
+
+        self.linear_error = Some(linear_error);
+        self.angular_error = Some(angular_error);
+        let linear_done = settings.linear_tolerances.update_all(linear_error);
+        let angular_done = settings.angular_tolerances.update_all(angular_error);
+        if linear_done && angular_done && self.is_near {
+            self.exit_reason = Some(MotionExitReason::Settled);
+            return MotionOutput::velocity(0.0, 0.0);
+        }
+        {
+            let is_robot_side: bool = (pose.position.y - target.position.y)
+                * -target.orientation.sin()
+                <= (pose.position.x - target.position.x) * target.orientation.cos()
+                    + self.params.early_exit_range;
+            let is_carrot_side: bool = (carrot_point.y - target.position.y)
+                * -target.orientation.sin()
+                <= (carrot_point.x - target.position.x) * target.orientation.cos()
+                    + self.params.early_exit_range;
+            let is_same_side: bool = is_robot_side == is_carrot_side;
+
+            if !is_same_side
+                && self.previous_was_same_side
+                && self.is_near
+                && self.params.min_linear_speed != 0.0
             {
-                let is_robot_side: bool = (pose.position.y - target.position.y)
-                    * -target.orientation.sin()
-                    <= (pose.position.x - target.position.x) * target.orientation.cos()
-                        + unwrapped_params.early_exit_range;
-                let is_carrot_side: bool = (carrot_point.y - target.position.y)
-                    * -target.orientation.sin()
-                    <= (carrot_point.x - target.position.x) * target.orientation.cos()
-                        + unwrapped_params.early_exit_range;
-                let is_same_side: bool = is_robot_side == is_carrot_side;
-
-                if !is_same_side
-                    && previous_was_same_side
-                    && is_near
-                    && unwrapped_params.min_linear_speed != 0.0
-                {
-                    break;
-                }
-                previous_was_same_side = is_same_side;
+                self.exit_reason = Some(MotionExitReason::EarlyExit);
+                return MotionOutput::velocity(0.0, 0.0);
             }
-            let angular_output = {
-                let mut motion_settings = self.motion_settings.boomerang_settings.borrow_mut();
-                let mut raw_output = if let Some(settings) = &mut settings {
-                    settings
-                } else {
-                    &mut *motion_settings
-                }
+            self.previous_was_same_side = is_same_side;
+        }
+        let angular_output = {
+            let mut raw_output = settings
                 .angular_controller
                 .update(angular_error, 0.0)
                 .clamp(
-                    -unwrapped_params.max_angular_speed,
-                    unwrapped_params.max_angular_speed,
+                    -self.params.max_angular_speed,
+                    self.params.max_angular_speed,
                 );
+            raw_output = delta_clamp(
+                raw_output,
+                self.previous_angular_output,
+                self.params.angular_slew.unwrap_or(0.0),
+                None,
+            );
+            self.previous_angular_output = raw_output;
+            raw_output
+        };
+        let linear_output = {
+            let mut raw_output = settings
+                .linear_controller
+                .update(linear_error, 0.0)
+                .clamp(-self.params.max_linear_speed, self.params.max_linear_speed);
+            if !self.is_near {
                 raw_output = delta_clamp(
                     raw_output,
-                    previous_angular_output,
-                    unwrapped_params.angular_slew.unwrap_or(0.0),
+                    self.previous_linear_output,
+                    self.params.linear_slew.unwrap_or(0.0),
                     None,
-                );
-                previous_angular_output = raw_output;
-                raw_output
-            };
-            let linear_output = {
-                let mut motion_settings = self.motion_settings.boomerang_settings.borrow_mut();
-                let mut raw_output = if let Some(settings) = &mut settings {
-                    settings
-                } else {
-                    &mut motion_settings
-                }
-                .linear_controller
-                .update(linear_error, 0.0)
-                .clamp(
-                    -unwrapped_params.max_linear_speed,
-                    unwrapped_params.max_linear_speed,
-                );
-                if !is_near {
-                    raw_output = delta_clamp(
-                        raw_output,
-                        previous_linear_output,
-                        unwrapped_params.linear_slew.unwrap_or(0.0),
-                        None,
-                    )
-                }
-                // Get radius. Calculate local error to carrot.
-                let carrot_error: nalgebra::Vector2<f64> =
-                    nalgebra::Rotation2::new(-pose.orientation) * (carrot_point - pose.position);
-                let half_arc = carrot_error.y / carrot_error.x.atan().abs();
-                let radius = carrot_error.norm() / (2.0 * half_arc.sin());
-                if let Some(horizontal_drift_compensation) =
-                    unwrapped_params.horizontal_drift_compensation
-                {
-                    let anti_drift_max_speed = (horizontal_drift_compensation * radius).sqrt();
-                    raw_output = raw_output.clamp(-anti_drift_max_speed, anti_drift_max_speed);
-                }
-
-                // Prevent moving in the wrong direction.
-                if !unwrapped_params.forwards && !is_near {
-                    raw_output = raw_output.min(0.0);
-                } else if unwrapped_params.forwards && !is_near {
-                    raw_output = raw_output.max(0.0);
-                }
-
-                if !unwrapped_params.forwards
-                    && -raw_output < unwrapped_params.min_linear_speed.abs()
-                    && raw_output < 0.0
-                {
-                    raw_output = -unwrapped_params.min_linear_speed.abs();
-                } else if unwrapped_params.forwards
-                    && raw_output < unwrapped_params.min_linear_speed.abs()
-                    && raw_output > 0.0
-                {
-                    raw_output = unwrapped_params.min_linear_speed.abs();
-                }
-                previous_linear_output = raw_output;
-                raw_output
-            };
-            let (left, right) = arcade_desaturate(linear_output, angular_output);
-            self.drivetrain
-                .left_motors
-                .borrow_mut()
-                .set_velocity_percentage_all(left);
-            self.drivetrain
-                .right_motors
-                .borrow_mut()
-                .set_velocity_percentage_all(right);
-
-            vexide::time::sleep(vexide::prelude::Motor::WRITE_INTERVAL).await;
-        }
-        *self.distance_traveled.borrow_mut() = None;
-        self.motion_handler.end_motion().await;
+                )
+            }
+            // Get radius. Calculate local error to carrot.
+            let carrot_error: nalgebra::Vector2<f64> =
+                nalgebra::Rotation2::new(-pose.orientation) * (carrot_point - pose.position);
+            let half_arc = carrot_error.y / carrot_error.x.atan().abs();
+            let radius = carrot_error.norm() / (2.0 * half_arc.sin());
+            if let Some(horizontal_drift_compensation) = self.params.horizontal_drift_compensation {
+                let anti_drift_max_speed = (horizontal_drift_compensation * radius).sqrt();
+                raw_output = raw_output.clamp(-anti_drift_max_speed, anti_drift_max_speed);
+            }
+
+            // Prevent moving in the wrong direction.
+            if !self.params.forwards && !self.is_near {
+                raw_output = raw_output.min(0.0);
+            } else if self.params.forwards && !self.is_near {
+                raw_output = raw_output.max(0.0);
+            }
+
+            if !self.params.forwards
+                && -raw_output < self.params.min_linear_speed
+                && raw_output < 0.0
+            {
+                raw_output = -self.params.min_linear_speed;
+            } else if self.params.forwards
+                && raw_output < self.params.min_linear_speed
+                && raw_output > 0.0
+            {
+                raw_output = self.params.min_linear_speed;
+            }
+            self.previous_linear_output = raw_output;
+            raw_output
+        };
+        log_telemetry(
+            "boomerang",
+            &[
+                ("linear", settings.linear_controller.as_ref()),
+                ("angular", settings.angular_controller.as_ref()),
+            ],
+        );
+        let (left, right) = arcade_desaturate(linear_output, angular_output);
+        MotionOutput::velocity(left, right)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.exit_reason.is_some()
+    }
+
+    fn exit_reason(&self) -> MotionExitReason {
+        self.exit_reason.unwrap_or(MotionExitReason::Settled)
+    }
+
+    fn linear_error(&self) -> Option<f64> {
+        self.linear_error
+    }
+
+    fn angular_error(&self) -> Option<f64> {
+        self.angular_error
+    }
+
+    fn exit_velocity(&self) -> (f64, f64) {
+        (self.previous_linear_output, self.previous_angular_output)
+    }
+
+    fn seed_velocity(&mut self, linear: f64, angular: f64) {
+        self.previous_linear_output = linear;
+        self.previous_angular_output = angular;
+    }
+
+    fn pass_through(&mut self, min_speed: f64, exit_range: f64) {
+        self.params.min_linear_speed = self.params.min_linear_speed.max(min_speed);
+        self.params.early_exit_range = self.params.early_exit_range.max(exit_range);
+    }
+}
+
+#[bon]
+impl<T: Tracking + 'static> Chassis<T> {
+    #[builder]
+    pub async fn boomerang(
+        self: Rc<Self>,
+        target: impl Into<MoveToPoseTarget> + 'static,
+        timeout: Option<Duration>,
+        params: Option<BoomerangParameters>,
+        settings: Option<BoomerangSettings>,
+        priority: Option<MotionPriority>,
+        run_async: Option<bool>,
+    ) -> MotionHandle {
+        let motion = BoomerangMotion::new(
+            target,
+            params.unwrap_or(params_boomerang!()),
+            self.settings_source(settings, |settings| &settings.boomerang_settings),
+        );
+        self.run_motion()
+            .motion(motion)
+            .maybe_timeout(timeout)
+            .maybe_priority(priority)
+            .maybe_run_async(run_async)
+            .call()
+            .await
     }
 }
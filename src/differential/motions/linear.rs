@@ -5,17 +5,17 @@ use core::{f64::consts::PI, time::Duration};
 use bon::{bon, Builder};
 use nalgebra::Vector2;
 use num_traits::AsPrimitive;
-use vexide::prelude::{Float, Motor};
+use vexide::prelude::Float;
 
-use super::ToleranceGroup;
+use super::{
+    log_telemetry, Motion, MotionExitReason, MotionHandle, MotionOutput, MotionPriority,
+    SettingsSource, ToleranceGroup,
+};
 use crate::{
     controllers::FeedbackController,
-    differential::chassis::Chassis,
+    differential::{chassis::Chassis, pose::Pose},
     tracking::Tracking,
-    utils::{
-        math::{arcade_desaturate, delta_clamp},
-        timer::Timer,
-    },
+    utils::math::{arcade_desaturate, delta_clamp},
 };
 
 #[derive(Clone, Copy, PartialEq, Builder)]
@@ -114,185 +114,198 @@ impl<T: AsPrimitive<f64>, U: AsPrimitive<f64>> From<(T, U)> for MoveToPointTarge
     }
 }
 
-#[bon]
-impl<T: Tracking + 'static> Chassis<T> {
-    #[builder]
-    // Prefer using RAMSETE hybrid due to lateral correction term, which still needs testing.
-    pub async fn move_to_point(
-        self: Rc<Self>,
-        target: impl Into<MoveToPointTarget> + 'static,
-        timeout: Option<Duration>,
-        params: Option<MoveToPointParameters>,
-        mut settings: Option<MoveToPointSettings>,
-        run_async: Option<bool>,
-    ) {
-        info!("Moving to point!");
-        self.motion_handler.wait_for_motions_end().await;
-        if !self.motion_handler.is_in_motion() {
-            return;
-        }
-        debug!("Received motion mutex.");
-        if run_async.unwrap_or(true) {
-            // Spawn vexide task
-            vexide::task::spawn({
-                let self_clone = self.clone();
-                async move {
-                    self_clone
-                        .move_to_point()
-                        .target(target)
-                        .maybe_timeout(timeout)
-                        .maybe_params(params)
-                        .maybe_settings(settings)
-                        .run_async(false)
-                        .call()
-                        .await
-                }
-            })
-            .detach();
-            self.motion_handler.end_motion().await;
-            vexide::time::sleep(Duration::from_millis(10)).await;
-            return;
-        }
+/// Drives to a point while turning to face it.
+pub struct MoveToPointMotion {
+    target: Vector2<f64>,
+    params: MoveToPointParameters,
+    settings: SettingsSource<MoveToPointSettings>,
+    is_near: bool,
+    previous_linear_output: f64,
+    previous_angular_output: f64,
+    linear_error: Option<f64>,
+    angular_error: Option<f64>,
+    exit_reason: Option<MotionExitReason>,
+}
 
-        if let Some(settings) = &mut settings {
-            settings.reset();
-        } else {
-            self.motion_settings
-                .move_to_point_settings
-                .borrow_mut()
-                .reset();
+impl MoveToPointMotion {
+    pub fn new(
+        target: impl Into<MoveToPointTarget>,
+        params: MoveToPointParameters,
+        settings: impl Into<SettingsSource<MoveToPointSettings>>,
+    ) -> Self {
+        Self {
+            target: target.into().0,
+            params,
+            settings: settings.into(),
+            is_near: false,
+            previous_linear_output: 0.0,
+            previous_angular_output: 0.0,
+            linear_error: None,
+            angular_error: None,
+            exit_reason: None,
         }
-        *self.distance_traveled.borrow_mut() = Some(0.0);
-        let target: Vector2<_> = target.into().0;
-        let mut unwrapped_params = params.unwrap_or(params_move_to_point!());
-        let mut previous_pose = self.pose().await;
-        let mut is_near: bool = false;
-        let mut previous_linear_output: f64 = 0.0;
-        let mut previous_angular_output: f64 = 0.0;
-
-        let mut timer = Timer::new(timeout.unwrap_or(Duration::MAX));
-        while !timer.is_done() && self.motion_handler.is_in_motion() {
-            let pose = self.pose().await;
-            if let Some(distance) = self.distance_traveled.borrow_mut().as_mut() {
-                *distance += pose.distance_to(&previous_pose);
-            }
-            previous_pose = pose;
-            let local_error = nalgebra::Rotation2::new(
-                -pose.orientation + if unwrapped_params.forwards { 0.0 } else { PI },
-            ) * (target - pose.position);
-            let linear_error = local_error.norm();
-            let cosine_linear_error = local_error.x;
-            let angular_error = local_error.y.atan2(local_error.x);
-            if local_error.norm() < 5.0 && !is_near {
-                unwrapped_params.max_linear_speed = 0.6;
-                is_near = true;
-            }
+    }
+}
 
-            if linear_error.abs() < unwrapped_params.early_exit_range {
-                break;
-            }
+impl Motion for MoveToPointMotion {
+    fn init(&mut self, _pose: Pose) {
+        self.settings.resolve().reset();
+    }
 
-            if if let Some(settings) = &mut settings {
-                settings.linear_tolerances.update_all(cosine_linear_error)
-            } else {
-                self.motion_settings
-                    .boomerang_settings
-                    .borrow_mut()
-                    .linear_tolerances
-                    .update_all(cosine_linear_error)
-            } && is_near
-            {
-                break;
-            }
-            let linear_output = {
-                if let Some(settings) = &mut settings {
-                    settings.linear_controller.update(cosine_linear_error, 0.0)
-                } else {
-                    self.motion_settings
-                        .move_to_point_settings
-                        .borrow_mut()
-                        .linear_controller
-                        .update(cosine_linear_error, 0.0)
-                }
-            }
-            .clamp(
-                -unwrapped_params.max_linear_speed,
-                unwrapped_params.max_linear_speed,
-            );
+    fn step(&mut self, pose: Pose) -> MotionOutput {
+        let settings = self.settings.resolve();
+        let local_error = nalgebra::Rotation2::new(
+            -pose.orientation + if self.params.forwards { 0.0 } else { PI },
+        ) * (self.target - pose.position);
+        let linear_error = local_error.norm();
+        let cosine_linear_error = local_error.x;
+        let angular_error = local_error.y.atan2(local_error.x);
+        self.linear_error = Some(linear_error);
+        self.angular_error = Some(angular_error);
+        if local_error.norm() < 5.0 && !self.is_near {
+            self.params.max_linear_speed = 0.6;
+            self.is_near = true;
+        }
 
-            let linear_output = {
-                let check_1_result = if (-unwrapped_params.min_linear_speed
-                    ..unwrapped_params.min_linear_speed)
-                    .contains(&linear_output)
-                {
-                    unwrapped_params.min_linear_speed
-                } else {
-                    linear_output
-                };
-                let check_2_result = if check_1_result < 0.0 && !is_near {
-                    0.0
-                } else {
-                    check_1_result
-                };
-                delta_clamp(
-                    check_2_result,
-                    previous_linear_output,
-                    unwrapped_params.linear_slew.unwrap_or(0.0),
-                    None,
-                )
+        if linear_error.abs() < self.params.early_exit_range {
+            self.exit_reason = Some(MotionExitReason::EarlyExit);
+            return MotionOutput::velocity(0.0, 0.0);
+        }
+        if settings.linear_tolerances.update_all(cosine_linear_error) && self.is_near {
+            self.exit_reason = Some(MotionExitReason::Settled);
+            return MotionOutput::velocity(0.0, 0.0);
+        }
+        let linear_output = settings
+            .linear_controller
+            .update(cosine_linear_error, 0.0)
+            .clamp(-self.params.max_linear_speed, self.params.max_linear_speed);
+
+        let linear_output = {
+            let check_1_result = if (-self.params.min_linear_speed..self.params.min_linear_speed)
+                .contains(&linear_output)
+            {
+                self.params.min_linear_speed
+            } else {
+                linear_output
+            };
+            let check_2_result = if check_1_result < 0.0 && !self.is_near {
+                0.0
+            } else {
+                check_1_result
             };
+            delta_clamp(
+                check_2_result,
+                self.previous_linear_output,
+                self.params.linear_slew.unwrap_or(0.0),
+                None,
+            )
+        };
 
-            let angular_output = {
-                if let Some(settings) = &mut settings {
-                    settings.angular_controller.update(angular_error, 0.0)
-                } else {
-                    self.motion_settings
-                        .move_to_point_settings
-                        .borrow_mut()
-                        .angular_controller
-                        .update(angular_error, 0.0)
-                }
-            }
+        let angular_output = settings
+            .angular_controller
+            .update(angular_error, 0.0)
             .clamp(
-                -unwrapped_params.max_angular_speed,
-                unwrapped_params.max_angular_speed,
-            );
-            let angular_output = delta_clamp(
-                angular_output,
-                previous_angular_output,
-                unwrapped_params.angular_slew.unwrap_or(0.0),
-                None,
+                -self.params.max_angular_speed,
+                self.params.max_angular_speed,
             );
+        let angular_output = delta_clamp(
+            angular_output,
+            self.previous_angular_output,
+            self.params.angular_slew.unwrap_or(0.0),
+            None,
+        );
+
+        self.previous_linear_output = linear_output;
+        self.previous_angular_output = angular_output;
+
+        let (left, right) = arcade_desaturate(
+            if self.params.forwards {
+                linear_output
+            } else {
+                -linear_output
+            },
+            angular_output,
+        );
+        log_telemetry(
+            "move_to_point",
+            &[
+                ("linear", settings.linear_controller.as_ref()),
+                ("angular", settings.angular_controller.as_ref()),
+            ],
+        );
+        info!(
+            "move_to_point: l. error: {}, l. output: {}, cos l. error: {}, a. error: {}, a.output: {}, left: {}, right: {}",
+            linear_error, linear_output, cosine_linear_error, angular_error, angular_output, left, right
+        );
+        MotionOutput::velocity(left, right)
+    }
 
-            previous_linear_output = linear_output;
-            previous_angular_output = angular_output;
+    fn is_finished(&self) -> bool {
+        self.exit_reason.is_some()
+    }
 
-            let (left, right) = arcade_desaturate(
-                if unwrapped_params.forwards {
-                    linear_output
-                } else {
-                    -linear_output
-                },
-                angular_output,
-            );
-            info!(
-                "move_to_point: l. error: {}, l. output: {}, cos l. error: {}, a. error: {}, a.output: {}, left: {}, right: {}",
-                linear_error, linear_output, cosine_linear_error, angular_error, angular_output, left, right
-            );
+    fn exit_reason(&self) -> MotionExitReason {
+        self.exit_reason.unwrap_or(MotionExitReason::Settled)
+    }
 
-            self.drivetrain
-                .left_motors
-                .borrow_mut()
-                .set_velocity_percentage_all(left);
-            self.drivetrain
-                .right_motors
-                .borrow_mut()
-                .set_velocity_percentage_all(right);
+    fn linear_error(&self) -> Option<f64> {
+        self.linear_error
+    }
 
-            vexide::time::sleep(Motor::WRITE_INTERVAL).await;
-        }
-        *self.distance_traveled.borrow_mut() = None;
-        self.motion_handler.end_motion().await;
+    fn angular_error(&self) -> Option<f64> {
+        self.angular_error
+    }
+
+    fn exit_velocity(&self) -> (f64, f64) {
+        let linear = if self.params.forwards {
+            self.previous_linear_output
+        } else {
+            -self.previous_linear_output
+        };
+        (linear, self.previous_angular_output)
+    }
+
+    fn seed_velocity(&mut self, linear: f64, angular: f64) {
+        self.previous_linear_output = if self.params.forwards {
+            linear
+        } else {
+            -linear
+        };
+        self.previous_angular_output = angular;
+    }
+
+    fn pass_through(&mut self, min_speed: f64, exit_range: f64) {
+        self.params.min_linear_speed = self.params.min_linear_speed.max(min_speed);
+        self.params.early_exit_range = self.params.early_exit_range.max(exit_range);
+    }
+}
+
+#[bon]
+impl<T: Tracking + 'static> Chassis<T> {
+    #[builder]
+    // Prefer using RAMSETE hybrid due to lateral correction term, which still needs testing.
+    pub async fn move_to_point(
+        self: Rc<Self>,
+        target: impl Into<MoveToPointTarget> + 'static,
+        timeout: Option<Duration>,
+        params: Option<MoveToPointParameters>,
+        settings: Option<MoveToPointSettings>,
+        priority: Option<MotionPriority>,
+        run_async: Option<bool>,
+    ) -> MotionHandle {
+        info!("Moving to point!");
+        let motion = MoveToPointMotion::new(
+            target,
+            params.unwrap_or(params_move_to_point!()),
+            self.settings_source(settings, |settings| &settings.move_to_point_settings),
+        );
+        self.run_motion()
+            .motion(motion)
+            .maybe_timeout(timeout)
+            .maybe_priority(priority)
+            .maybe_run_async(run_async)
+            .call()
+            .await
     }
 
     #[builder]
@@ -302,14 +315,16 @@ impl<T: Tracking + 'static> Chassis<T> {
         timeout: Option<Duration>,
         params: Option<MoveRelativeParameters>,
         settings: Option<MoveRelativeSettings>,
+        priority: Option<MotionPriority>,
         run_async: Option<bool>,
-    ) {
+    ) -> MotionHandle {
         info!("Moving relative: {}", distance);
-        // Wait until the motion is done. before calculating angles.
-        if self.motion_handler.is_in_motion() {
+        // Wait until the queued motions are done before calculating angles, unless
+        // this cancels them.
+        if priority != Some(MotionPriority::Preempt) {
             self.wait_until_complete().await;
+            debug!("Previous motion was done.");
         }
-        debug!("Previous motion was done.");
         let pose = self.pose().await;
         let displacement_vector =
             nalgebra::Rotation2::new(pose.orientation) * Vector2::new(distance, 0.0);
@@ -330,8 +345,9 @@ impl<T: Tracking + 'static> Chassis<T> {
             .maybe_timeout(timeout)
             .params(move_to_point_params)
             .maybe_settings(settings)
+            .maybe_priority(priority)
             .maybe_run_async(run_async)
             .call()
-            .await;
+            .await
     }
 }
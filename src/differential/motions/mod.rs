@@ -1,100 +1,759 @@
 #[macro_use]
 pub mod angular;
+pub mod autotune;
 #[macro_use]
 pub mod linear;
 #[macro_use]
 pub mod boomerang;
 #[macro_use]
+pub mod pure_pursuit;
+#[macro_use]
 pub mod ramsete;
+pub mod sequence;
+pub mod trajectory;
+pub mod trigger;
 
-use alloc::{collections::VecDeque, vec::Vec};
+use alloc::{boxed::Box, collections::VecDeque, rc::Rc, vec::Vec};
 use log::debug;
-use core::{cell::RefCell, time::Duration};
+use core::{
+    cell::RefCell,
+    future::{poll_fn, Future},
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use bon::bon;
+use vexide::prelude::{BrakeMode, Motor, MotorControl};
 
-use vexide::{sync::Mutex, time::Instant};
+use crate::{
+    controllers::FeedbackController,
+    differential::{
+        chassis::{Chassis, MotionSettings},
+        pose::Pose,
+    },
+    tracking::Tracking,
+    utils::{
+        clock::{vex_clock, Clock},
+        timer::Timer,
+    },
+};
 
-struct BlockingQueue {
-    /// It is locked when there is a value as the first element of the queue.
-    queue: Mutex<VecDeque<usize>>,
-    counter: RefCell<usize>,
+/// Logs the telemetry of a motion's controllers at the debug level, for those that report it.
+fn log_telemetry(motion: &str, controllers: &[(&str, &dyn FeedbackController<f64>)]) {
+    for (name, controller) in controllers {
+        if let Some(telemetry) = controller.telemetry() {
+            debug!("{} {}: {}", motion, name, telemetry);
+        }
+    }
 }
 
-impl BlockingQueue {
-    fn new() -> Self {
+/// What a [`Motion`] commands one side of the drivetrain to do.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SideOutput {
+    /// A fraction of the maximum velocity, between -1 and 1.
+    Velocity(f64),
+
+    /// A fraction of the maximum voltage, between -1 and 1.
+    Voltage(f64),
+
+    Brake(BrakeMode),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MotionOutput {
+    pub left: SideOutput,
+    pub right: SideOutput,
+}
+
+impl MotionOutput {
+    pub fn velocity(left: f64, right: f64) -> Self {
         Self {
-            queue: Mutex::new(VecDeque::new()),
-            counter: RefCell::new(0),
+            left: SideOutput::Velocity(left),
+            right: SideOutput::Velocity(right),
         }
     }
 
-    async fn take(&self) {
-        let id = {
-            let mut counter = self.counter.borrow_mut();
-            let id = *counter;
-            *counter += 1;
-            id
-        };
+    pub fn voltage(left: f64, right: f64) -> Self {
+        Self {
+            left: SideOutput::Voltage(left),
+            right: SideOutput::Voltage(right),
+        }
+    }
+
+    pub fn brake(brake_mode: BrakeMode) -> Self {
+        Self {
+            left: SideOutput::Brake(brake_mode),
+            right: SideOutput::Brake(brake_mode),
+        }
+    }
+}
+
+/// Why a motion ended.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MotionExitReason {
+    /// The errors settled within the tolerances, or the motion reached its end.
+    Settled,
+
+    Timeout,
+
+    /// The motion exited before settling, such as through `early_exit_range`.
+    EarlyExit,
+
+    /// The motion was cancelled, including before it started.
+    Cancelled,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MotionResult {
+    pub exit_reason: MotionExitReason,
+    pub elapsed: Duration,
+
+    /// The distance traveled as measured by [`Motion::distance_between`].
+    pub distance_traveled: f64,
+
+    /// The linear error in inches at the last step, for motions that have one.
+    pub linear_error: Option<f64>,
+
+    /// The angular error in radians at the last step, for motions that have one.
+    pub angular_error: Option<f64>,
+}
+
+impl MotionResult {
+    fn cancelled() -> Self {
+        Self {
+            exit_reason: MotionExitReason::Cancelled,
+            elapsed: Duration::ZERO,
+            distance_traveled: 0.0,
+            linear_error: None,
+            angular_error: None,
+        }
+    }
+
+    /// Whether the motion settled or exited early rather than timing out or
+    /// being cancelled.
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self.exit_reason,
+            MotionExitReason::Settled | MotionExitReason::EarlyExit
+        )
+    }
+}
+
+#[derive(Default)]
+struct MotionHandleState {
+    result: Option<MotionResult>,
+    wakers: Vec<Waker>,
+    triggers: Vec<trigger::Trigger>,
+}
+
+/// Resolves to the [`MotionResult`] of a motion once it ends. Motions that run
+/// asynchronously return right away, and the handle can be awaited later or used to
+/// register triggers partway through the motion.
+///
+/// # Example
+///
+/// ```
+/// let handle = chassis.clone().turn_to().target(90.0).call().await;
+/// intake.lock().await.spin();
+/// if handle.await.exit_reason == MotionExitReason::Timeout {
+///     return;
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct MotionHandle {
+    state: Rc<RefCell<MotionHandleState>>,
+}
+
+impl MotionHandle {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn complete(&self, result: MotionResult) {
         {
-            let mut lock = self.queue.lock().await;
-            lock.push_back(id);
-            debug!("Blocking queue identifier: {:?}", lock.front().copied());
+            let mut state = self.state.borrow_mut();
+            state.result = Some(result);
+            for waker in state.wakers.drain(..) {
+                waker.wake();
+            }
+        }
+        self.release_triggers();
+    }
+
+    /// The result of the motion, if it has ended.
+    pub fn result(&self) -> Option<MotionResult> {
+        self.state.borrow().result
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.result().is_some()
+    }
+}
+
+impl Future for MotionHandle {
+    type Output = MotionResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+        if let Some(result) = state.result {
+            return Poll::Ready(result);
+        }
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
         }
-        loop {
-            if self.queue.lock().await.front().copied() == Some(id) {
-                debug!("Identifier {} reached the start of the queue.", id);
-                return;
+        Poll::Pending
+    }
+}
+
+/// A motion for [`Chassis::run_motion`], which takes care of queueing it behind other
+/// motions, spawning it asynchronously, timeouts and cancellation.
+///
+/// Each step, the pose is measured and passed to [`Motion::step`], and its output is
+/// applied to the drivetrain unless [`Motion::is_finished`] has become true.
+///
+/// # Example
+///
+/// ```
+/// struct DriveFor(Timer);
+///
+/// impl Motion for DriveFor {
+///     fn step(&mut self, _pose: Pose) -> MotionOutput {
+///         MotionOutput::velocity(0.5, 0.5)
+///     }
+///
+///     fn is_finished(&self) -> bool {
+///         self.0.is_done()
+///     }
+/// }
+///
+/// chassis
+///     .clone()
+///     .run_motion()
+///     .motion(DriveFor(Timer::new(Duration::from_secs(1))))
+///     .call()
+///     .await;
+/// ```
+pub trait Motion {
+    /// Called once with the starting pose, before the first step.
+    fn init(&mut self, _pose: Pose) {}
+
+    /// The output for the drivetrain at the current pose.
+    fn step(&mut self, pose: Pose) -> MotionOutput;
+
+    /// Whether the motion has settled or exited early. It is checked after each step,
+    /// before the output of the step is applied.
+    fn is_finished(&self) -> bool;
+
+    /// Why the motion finished, once [`Motion::is_finished`] is true. This is
+    /// [`MotionExitReason::Settled`] by default.
+    fn exit_reason(&self) -> MotionExitReason {
+        MotionExitReason::Settled
+    }
+
+    /// Called once when the motion ends. The output returned, if any, is applied
+    /// to the drivetrain unless the motion was cancelled.
+    fn on_exit(&mut self) -> Option<MotionOutput> {
+        None
+    }
+
+    /// The linear error in inches at the last step, if the motion has one.
+    fn linear_error(&self) -> Option<f64> {
+        None
+    }
+
+    /// The angular error in radians at the last step, if the motion has one.
+    fn angular_error(&self) -> Option<f64> {
+        None
+    }
+
+    /// The progress between two poses that is added to the distance traveled for
+    /// [`Chassis::wait_until`], which is the distance between them by default.
+    fn distance_between(&self, previous_pose: Pose, pose: Pose) -> f64 {
+        pose.distance_to(&previous_pose)
+    }
+
+    /// The linear and angular outputs of the last step as fractions of the maximum
+    /// velocity, counterclockwise positive, for the next segment of a
+    /// [`sequence::MotionSequence`] to continue from.
+    fn exit_velocity(&self) -> (f64, f64) {
+        (0.0, 0.0)
+    }
+
+    /// Continues from the exit velocity of the segment before it in a sequence.
+    /// It is called right after [`Motion::init`].
+    fn seed_velocity(&mut self, _linear: f64, _angular: f64) {}
+
+    /// Makes the motion pass through its target instead of settling, for all but
+    /// the last segment of a sequence. It should keep at least `min_speed` and exit
+    /// within `exit_range` inches of the target.
+    fn pass_through(&mut self, _min_speed: f64, _exit_range: f64) {}
+}
+
+impl<M: Motion + ?Sized> Motion for Box<M> {
+    fn init(&mut self, pose: Pose) {
+        (**self).init(pose);
+    }
+
+    fn step(&mut self, pose: Pose) -> MotionOutput {
+        (**self).step(pose)
+    }
+
+    fn is_finished(&self) -> bool {
+        (**self).is_finished()
+    }
+
+    fn exit_reason(&self) -> MotionExitReason {
+        (**self).exit_reason()
+    }
+
+    fn on_exit(&mut self) -> Option<MotionOutput> {
+        (**self).on_exit()
+    }
+
+    fn linear_error(&self) -> Option<f64> {
+        (**self).linear_error()
+    }
+
+    fn angular_error(&self) -> Option<f64> {
+        (**self).angular_error()
+    }
+
+    fn distance_between(&self, previous_pose: Pose, pose: Pose) -> f64 {
+        (**self).distance_between(previous_pose, pose)
+    }
+
+    fn exit_velocity(&self) -> (f64, f64) {
+        (**self).exit_velocity()
+    }
+
+    fn seed_velocity(&mut self, linear: f64, angular: f64) {
+        (**self).seed_velocity(linear, angular);
+    }
+
+    fn pass_through(&mut self, min_speed: f64, exit_range: f64) {
+        (**self).pass_through(min_speed, exit_range);
+    }
+}
+
+/// The settings of a motion.
+pub enum SettingsSource<S> {
+    /// Settings given for this motion alone.
+    Fixed(S),
+
+    /// Reads the defaults of the chassis when the motion starts rather than when it
+    /// is created, so that a queued motion uses any gains or tolerances set while it
+    /// waits.
+    Default(Box<dyn Fn() -> S>),
+}
+
+impl<S> SettingsSource<S> {
+    /// The settings, reading the defaults the first time this is called. Motions call
+    /// this in [`Motion::init`].
+    pub fn resolve(&mut self) -> &mut S {
+        if let Self::Default(read) = self {
+            *self = Self::Fixed(read());
+        }
+        match self {
+            Self::Fixed(settings) => settings,
+            Self::Default(_) => unreachable!(),
+        }
+    }
+
+    /// The settings, if the defaults have been read or were not needed.
+    pub fn resolved(&self) -> Option<&S> {
+        match self {
+            Self::Fixed(settings) => Some(settings),
+            Self::Default(_) => None,
+        }
+    }
+}
+
+impl<S> From<S> for SettingsSource<S> {
+    fn from(settings: S) -> Self {
+        Self::Fixed(settings)
+    }
+}
+
+#[bon]
+impl<T: Tracking + 'static> Chassis<T> {
+    /// Queues a motion to run once the motions before it have ended, until it
+    /// finishes, times out or is cancelled. With `run_async`, which is the default,
+    /// it runs in a spawned task and the returned handle resolves once it ends.
+    #[builder]
+    pub async fn run_motion(
+        self: Rc<Self>,
+        motion: impl Motion + 'static,
+        timeout: Option<Duration>,
+        priority: Option<MotionPriority>,
+        run_async: Option<bool>,
+    ) -> MotionHandle {
+        let ticket = self.motion_handler.enqueue(priority.unwrap_or_default());
+        let handle = MotionHandle::new();
+        if run_async.unwrap_or(true) {
+            // Spawn vexide task
+            vexide::task::spawn({
+                let self_clone = self.clone();
+                let handle = handle.clone();
+                async move {
+                    let result = self_clone
+                        .execute_motion(ticket, motion, timeout, &handle)
+                        .await;
+                    handle.complete(result);
+                }
+            })
+            .detach();
+            return handle;
+        }
+        let result = self.execute_motion(ticket, motion, timeout, &handle).await;
+        handle.complete(result);
+        handle
+    }
+
+    /// The settings given for a motion, or else the defaults selected from the
+    /// motion settings of the chassis.
+    pub(super) fn settings_source<S: Clone + 'static>(
+        self: &Rc<Self>,
+        settings: Option<S>,
+        select: fn(&MotionSettings) -> &RefCell<S>,
+    ) -> SettingsSource<S> {
+        match settings {
+            Some(settings) => SettingsSource::Fixed(settings),
+            None => {
+                let chassis = self.clone();
+                SettingsSource::Default(Box::new(move || {
+                    select(&chassis.motion_settings).borrow().clone()
+                }))
+            }
+        }
+    }
+}
+
+impl<T: Tracking> Chassis<T> {
+    async fn execute_motion(
+        &self,
+        ticket: MotionTicket,
+        mut motion: impl Motion,
+        timeout: Option<Duration>,
+        handle: &MotionHandle,
+    ) -> MotionResult {
+        if !self.motion_handler.wait_for_turn(&ticket).await {
+            return MotionResult::cancelled();
+        }
+        if !self.motion_handler.is_in_motion() {
+            self.motion_handler.end_motion(ticket);
+            return MotionResult::cancelled();
+        }
+        self.release_heading_hold();
+
+        let mut previous_pose = self.pose().await;
+        motion.init(previous_pose);
+        let mut distance_traveled = 0.0;
+        *self.distance_traveled.borrow_mut() = Some(distance_traveled);
+
+        let mut timer = Timer::new(timeout.unwrap_or(Duration::MAX));
+        let mut exit_reason = MotionExitReason::Timeout;
+        while !timer.is_done() {
+            if !self.motion_handler.is_in_motion() {
+                exit_reason = MotionExitReason::Cancelled;
+                break;
+            }
+            let pose = self.pose().await;
+            distance_traveled += motion.distance_between(previous_pose, pose);
+            *self.distance_traveled.borrow_mut() = Some(distance_traveled);
+            previous_pose = pose;
+            handle.update_triggers(pose, distance_traveled, timer.elapsed_time());
+
+            let output = motion.step(pose);
+            if motion.is_finished() {
+                exit_reason = motion.exit_reason();
+                break;
+            }
+            self.apply_motion_output(output);
+
+            vexide::time::sleep(Motor::WRITE_INTERVAL).await;
+        }
+
+        if let Some(output) = motion.on_exit() {
+            if exit_reason != MotionExitReason::Cancelled {
+                self.apply_motion_output(output);
             }
-            vexide::time::sleep(Duration::from_millis(100)).await;
         }
+        let result = MotionResult {
+            exit_reason,
+            elapsed: timer.elapsed_time(),
+            distance_traveled,
+            linear_error: motion.linear_error(),
+            angular_error: motion.angular_error(),
+        };
+        debug!("Motion ended: {:?}", result);
+        *self.distance_traveled.borrow_mut() = None;
+        self.motion_handler.end_motion(ticket);
+        result
     }
 
-    async fn give(&self) {
-        self.queue.lock().await.pop_front();
+    fn apply_motion_output(&self, output: MotionOutput) {
+        for (motors, side_output) in [
+            (&self.drivetrain.left_motors, output.left),
+            (&self.drivetrain.right_motors, output.right),
+        ] {
+            let mut motors = motors.borrow_mut();
+            match side_output {
+                SideOutput::Velocity(velocity) => {
+                    motors.set_velocity_percentage_all(velocity);
+                }
+                SideOutput::Voltage(voltage) => {
+                    motors.set_voltage_all_for_types(
+                        voltage * Motor::V5_MAX_VOLTAGE,
+                        voltage * Motor::EXP_MAX_VOLTAGE,
+                    );
+                }
+                SideOutput::Brake(brake_mode) => {
+                    motors.set_target_all(MotorControl::Brake(brake_mode));
+                }
+            }
+        }
     }
 }
 
-pub struct MotionHandler {
-    is_in_motion: RefCell<bool>,
-    is_in_queue: RefCell<bool>,
+/// Where a motion goes in the queue of the [`MotionHandler`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MotionPriority {
+    /// Runs after all the motions queued before it.
+    #[default]
+    Normal,
+
+    /// Runs before the queued motions with normal priority.
+    High,
+
+    /// Cancels the running motion and runs next, before the rest of the queue.
+    Preempt,
+}
+
+/// A motion's place in the queue of the [`MotionHandler`]. Dropping it, such as
+/// when the future running the motion is dropped, ends the motion if it is running
+/// or else removes it from the queue.
+pub struct MotionTicket {
+    id: usize,
+    queue: Rc<RefCell<MotionQueue>>,
+}
 
-    mutex: BlockingQueue,
+impl core::fmt::Debug for MotionTicket {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("MotionTicket").field("id", &self.id).finish()
+    }
+}
+
+impl Drop for MotionTicket {
+    fn drop(&mut self) {
+        self.queue.borrow_mut().release(self.id);
+    }
+}
+
+struct QueuedMotion {
+    id: usize,
+    priority: MotionPriority,
+    waker: Option<Waker>,
+}
+
+#[derive(Clone, Copy)]
+struct RunningMotion {
+    id: usize,
+    is_cancelled: bool,
+}
+
+#[derive(Default)]
+struct MotionQueue {
+    next_id: usize,
+    running: Option<RunningMotion>,
+    pending: VecDeque<QueuedMotion>,
+
+    /// Woken whenever a motion ends or the queue is cleared.
+    end_wakers: Vec<Waker>,
+}
+
+impl MotionQueue {
+    /// Ends the motion if it is running, or else removes it from the queue.
+    fn release(&mut self, id: usize) {
+        if self.running.is_some_and(|running| running.id == id) {
+            self.running = None;
+            self.start_next();
+        } else {
+            self.pending.retain(|motion| motion.id != id);
+        }
+        self.wake_end_wakers();
+    }
+
+    fn start_next(&mut self) {
+        if self.running.is_some() {
+            return;
+        }
+        if let Some(next) = self.pending.pop_front() {
+            debug!("Motion {} reached the start of the queue.", next.id);
+            self.running = Some(RunningMotion {
+                id: next.id,
+                is_cancelled: false,
+            });
+            if let Some(waker) = next.waker {
+                waker.wake();
+            }
+        }
+    }
+
+    fn wake_end_wakers(&mut self) {
+        for waker in self.end_wakers.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+/// Runs motions one at a time in the order they were queued. Motions waiting for
+/// their turn are woken as soon as the motion before them ends.
+pub struct MotionHandler {
+    queue: Rc<RefCell<MotionQueue>>,
 }
 
 impl MotionHandler {
     pub fn new() -> Self {
         Self {
-            is_in_motion: RefCell::new(false),
-            is_in_queue: RefCell::new(false),
-            mutex: BlockingQueue::new(),
+            queue: Rc::new(RefCell::new(MotionQueue::default())),
         }
     }
+
+    /// Whether a motion is running and has not been cancelled.
     pub fn is_in_motion(&self) -> bool {
-        *self.is_in_motion.borrow()
+        self.queue
+            .borrow()
+            .running
+            .is_some_and(|running| !running.is_cancelled)
     }
-    pub async fn wait_for_motions_end(&self) {
-        if *self.is_in_motion.borrow() {
-            *self.is_in_queue.borrow_mut() = true;
-        } else {
-            *self.is_in_motion.borrow_mut() = true;
+
+    /// The number of motions waiting behind the running one.
+    pub fn queued_motions(&self) -> usize {
+        self.queue.borrow().pending.len()
+    }
+
+    /// Adds a motion to the queue, and starts it right away if no motion is running.
+    /// It runs once [`MotionHandler::wait_for_turn`] returns, until it is ended with
+    /// [`MotionHandler::end_motion`] or the ticket is dropped.
+    pub fn enqueue(&self, priority: MotionPriority) -> MotionTicket {
+        let mut queue = self.queue.borrow_mut();
+        let id = queue.next_id;
+        queue.next_id += 1;
+        let index = match priority {
+            MotionPriority::Normal => queue.pending.len(),
+            MotionPriority::High => queue
+                .pending
+                .iter()
+                .position(|motion| motion.priority == MotionPriority::Normal)
+                .unwrap_or(queue.pending.len()),
+            MotionPriority::Preempt => {
+                if let Some(running) = queue.running.as_mut() {
+                    running.is_cancelled = true;
+                }
+                queue
+                    .pending
+                    .iter()
+                    .position(|motion| motion.priority != MotionPriority::Preempt)
+                    .unwrap_or(queue.pending.len())
+            }
+        };
+        queue.pending.insert(
+            index,
+            QueuedMotion {
+                id,
+                priority,
+                waker: None,
+            },
+        );
+        debug!("Queued motion {} at position {}.", id, index);
+        queue.start_next();
+        MotionTicket {
+            id,
+            queue: self.queue.clone(),
         }
-        self.mutex.take().await;
     }
-    pub async fn end_motion(&self) {
-        *self.is_in_motion.borrow_mut() = *self.is_in_queue.borrow();
-        *self.is_in_queue.borrow_mut() = false;
 
-        self.mutex.give().await;
+    /// Waits until the motion reaches the start of the queue and is running.
+    /// Returns `false` if it was cleared from the queue first.
+    pub async fn wait_for_turn(&self, ticket: &MotionTicket) -> bool {
+        poll_fn(|cx| {
+            let mut queue = self.queue.borrow_mut();
+            if queue.running.is_some_and(|running| running.id == ticket.id) {
+                return Poll::Ready(true);
+            }
+            match queue
+                .pending
+                .iter_mut()
+                .find(|motion| motion.id == ticket.id)
+            {
+                Some(motion) => {
+                    motion.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+                None => Poll::Ready(false),
+            }
+        })
+        .await
+    }
+
+    /// Ends the running motion and starts the next one in the queue. This is the
+    /// same as dropping the ticket.
+    pub fn end_motion(&self, ticket: MotionTicket) {
+        drop(ticket);
     }
 
+    /// Removes the motions that have not started yet, which end as cancelled.
+    pub fn clear_queue(&self) {
+        let mut queue = self.queue.borrow_mut();
+        for motion in queue.pending.drain(..) {
+            if let Some(waker) = motion.waker {
+                waker.wake();
+            }
+        }
+        queue.wake_end_wakers();
+    }
+
+    /// Waits until no motion is running or queued.
+    pub async fn wait_until_idle(&self) {
+        poll_fn(|cx| {
+            let mut queue = self.queue.borrow_mut();
+            if queue.running.is_none() && queue.pending.is_empty() {
+                Poll::Ready(())
+            } else {
+                queue.end_wakers.push(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Clears the queue, then cancels the running motion and waits for it to end.
     pub async fn cancel_all_motions(&self) {
-        *self.is_in_motion.borrow_mut() = false;
-        *self.is_in_queue.borrow_mut() = false;
-        vexide::time::sleep(Duration::from_millis(10)).await;
+        self.clear_queue();
+        self.cancel_motion().await;
     }
 
+    /// Cancels the running motion and waits for it to end. The queued motions
+    /// still run after it.
     pub async fn cancel_motion(&self) {
-        *self.is_in_motion.borrow_mut() = false;
-        vexide::time::sleep(Duration::from_millis(10)).await;
+        let id = match self.queue.borrow_mut().running.as_mut() {
+            Some(running) => {
+                running.is_cancelled = true;
+                running.id
+            }
+            None => return,
+        };
+        poll_fn(|cx| {
+            let mut queue = self.queue.borrow_mut();
+            if queue.running.is_some_and(|running| running.id == id) {
+                queue.end_wakers.push(cx.waker().clone());
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        })
+        .await
     }
 }
 
@@ -106,8 +765,9 @@ impl Default for MotionHandler {
 pub struct Tolerance<T> {
     range: T,
     timeout: Duration,
-    start_time: Option<Instant>,
+    start_time: Option<Duration>,
     done: bool,
+    clock: Rc<dyn Clock>,
 }
 
 impl<T: num_traits::Float + Copy> Tolerance<T> {
@@ -115,11 +775,11 @@ impl<T: num_traits::Float + Copy> Tolerance<T> {
         if error.abs() > self.range {
             self.start_time = None;
         } else if let Some(start_time) = self.start_time {
-            if Instant::now() > start_time + self.timeout {
+            if self.clock.now() > start_time + self.timeout {
                 self.done = true;
             }
         } else {
-            self.start_time = Some(Instant::now());
+            self.start_time = Some(self.clock.now());
         }
         self.done
     }
@@ -132,9 +792,16 @@ impl<T> Tolerance<T> {
             timeout,
             start_time: None,
             done: false,
+            clock: vex_clock(),
         }
     }
 
+    /// Measures how long the error has been in range with `clock`.
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     pub fn exit_state(&self) -> bool {
         self.done
     }
@@ -152,6 +819,7 @@ impl<T: Copy> Clone for Tolerance<T> {
             timeout: self.timeout,
             start_time: None,
             done: false,
+            clock: self.clock.clone(),
         }
     }
 }
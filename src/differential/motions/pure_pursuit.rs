@@ -0,0 +1,326 @@
+use alloc::{boxed::Box, rc::Rc};
+use core::{f64::consts::PI, time::Duration};
+
+use bon::{bon, Builder};
+use log::info;
+use nalgebra::Vector2;
+
+use super::{
+    log_telemetry, Motion, MotionExitReason, MotionHandle, MotionOutput, MotionPriority,
+    SettingsSource, ToleranceGroup,
+};
+use crate::{
+    controllers::FeedbackController,
+    differential::{chassis::Chassis, path::waypoint::WaypointPath, pose::Pose},
+    tracking::Tracking,
+    utils::math::{arcade_desaturate, delta_clamp},
+};
+
+#[derive(Clone, Copy, PartialEq, Builder)]
+pub struct PurePursuitParameters {
+    #[builder(default = true)]
+    pub forwards: bool,
+
+    #[builder(default = 0.0)]
+    pub min_linear_speed: f64,
+
+    #[builder(default = 1.0)]
+    pub max_linear_speed: f64,
+
+    /// The lookahead distance in inches when stopped.
+    #[builder(default = 6.0)]
+    pub min_lookahead: f64,
+
+    #[builder(default = 15.0)]
+    pub max_lookahead: f64,
+
+    /// How much the lookahead distance grows with speed, in inches per unit of speed.
+    #[builder(default = 12.0)]
+    pub lookahead_gain: f64,
+
+    /// How much curvature slows the robot down. The speed is limited to the maximum
+    /// speed divided by `1 + curvature_speed_scalar * |curvature|`, with the curvature
+    /// in inverse inches.
+    #[builder(default = 6.0)]
+    pub curvature_speed_scalar: f64,
+
+    #[builder(default = 0.0)]
+    pub early_exit_range: f64,
+    pub linear_slew: Option<f64>,
+}
+
+#[macro_export]
+macro_rules! params_pure_pursuit {
+    (
+        $($key:ident : $value:expr),* $(,)?
+    ) => {
+        $crate::differential::motions::pure_pursuit::PurePursuitParameters::builder()
+            $(.$key($value))*
+            .build()
+    };
+}
+pub use params_pure_pursuit;
+
+#[derive(Clone)]
+pub struct PurePursuitSettings {
+    /// Slows the robot down over the distance remaining along the path.
+    linear_controller: Box<dyn FeedbackController<f64>>,
+    linear_tolerances: ToleranceGroup<f64>,
+
+    /// Distance between the left and right wheels in inches.
+    track_width: f64,
+}
+
+impl PurePursuitSettings {
+    pub fn new(
+        linear_controller: Box<dyn FeedbackController<f64>>,
+        linear_tolerances: ToleranceGroup<f64>,
+        track_width: f64,
+    ) -> Self {
+        Self {
+            linear_controller,
+            linear_tolerances,
+            track_width,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.linear_controller.reset();
+        self.linear_tolerances.reset();
+    }
+}
+
+/// Follows a path with pure pursuit, steering towards a point a lookahead distance
+/// further along the path. The lookahead grows with speed, and the robot slows
+/// down for curvature, for the speeds of the waypoints and for the end of the path.
+pub struct PurePursuitMotion {
+    path: WaypointPath,
+    path_length: f64,
+    last_segment_start: f64,
+    end_point: Vector2<f64>,
+    params: PurePursuitParameters,
+    settings: SettingsSource<PurePursuitSettings>,
+    progress: f64,
+    previous_linear_output: f64,
+
+    /// Keeps the minimum speed along the last segment of the path.
+    is_passing_through: bool,
+    linear_error: Option<f64>,
+    exit_reason: Option<MotionExitReason>,
+}
+
+impl PurePursuitMotion {
+    /// # Panics
+    ///
+    /// Panics if the minimum linear speed exceeds the maximum, or if the lookahead
+    /// distances are not positive with the minimum not exceeding the maximum.
+    pub fn new(
+        path: impl Into<WaypointPath>,
+        mut params: PurePursuitParameters,
+        settings: impl Into<SettingsSource<PurePursuitSettings>>,
+    ) -> Self {
+        params.min_linear_speed = params.min_linear_speed.abs();
+        params.max_linear_speed = params.max_linear_speed.abs();
+        assert!(
+            params.max_linear_speed >= params.min_linear_speed,
+            "Minimum speed may not exceed the maximum."
+        );
+        assert!(
+            params.max_lookahead >= params.min_lookahead && params.min_lookahead > 0.0,
+            "The lookahead distances must be positive, with the minimum not exceeding the maximum."
+        );
+        let path: WaypointPath = path.into();
+        let path_length = path.length();
+        let last_segment_start = path.last_segment_start();
+        let end_point = path.waypoints()[path.waypoints().len() - 1].position;
+        Self {
+            path,
+            path_length,
+            last_segment_start,
+            end_point,
+            params,
+            settings: settings.into(),
+            progress: 0.0,
+            previous_linear_output: 0.0,
+            is_passing_through: false,
+            linear_error: None,
+            exit_reason: None,
+        }
+    }
+}
+
+impl Motion for PurePursuitMotion {
+    fn init(&mut self, _pose: Pose) {
+        self.settings.resolve().reset();
+    }
+
+    fn step(&mut self, pose: Pose) -> MotionOutput {
+        let settings = self.settings.resolve();
+        let params = self.params;
+        let to_local =
+            nalgebra::Rotation2::new(-pose.orientation + if params.forwards { 0.0 } else { PI });
+
+        self.progress = self.path.closest_distance(
+            pose.position,
+            self.progress,
+            (self.progress + params.max_lookahead).min(self.path_length),
+        );
+        let progress = self.progress;
+        let is_on_last_segment = progress >= self.last_segment_start;
+
+        // Along the last segment, the remaining distance is signed so that the
+        // robot backs up if it overshoots the end.
+        let remaining_distance = if is_on_last_segment {
+            (to_local * (self.end_point - pose.position)).x
+        } else {
+            self.path_length - progress
+        };
+        self.linear_error = Some(remaining_distance);
+        if is_on_last_segment {
+            if remaining_distance.abs() < params.early_exit_range {
+                self.exit_reason = Some(MotionExitReason::EarlyExit);
+                return MotionOutput::velocity(0.0, 0.0);
+            }
+            if settings.linear_tolerances.update_all(remaining_distance) {
+                self.exit_reason = Some(MotionExitReason::Settled);
+                return MotionOutput::velocity(0.0, 0.0);
+            }
+        }
+
+        let lookahead = (params.min_lookahead
+            + params.lookahead_gain * self.previous_linear_output.abs())
+        .min(params.max_lookahead);
+        let lookahead_error = to_local * (self.path.point_at(progress + lookahead) - pose.position);
+        let lookahead_distance_squared = lookahead_error.norm_squared();
+        let curvature = if lookahead_distance_squared > 0.0 {
+            2.0 * lookahead_error.y / lookahead_distance_squared
+        } else {
+            0.0
+        };
+
+        let speed_limit = self.path.speed_at(progress, params.max_linear_speed).min(
+            params.max_linear_speed
+                / (1.0
+                    + params.curvature_speed_scalar
+                        * curvature.abs().max(self.path.curvature_at(progress))),
+        );
+        let linear_output = settings
+            .linear_controller
+            .update(remaining_distance, 0.0)
+            .clamp(-speed_limit, speed_limit);
+        let linear_output = {
+            let check_1_result = if (-params.min_linear_speed..params.min_linear_speed)
+                .contains(&linear_output)
+                && (!is_on_last_segment || self.is_passing_through)
+            {
+                params.min_linear_speed
+            } else {
+                linear_output
+            };
+            delta_clamp(
+                check_1_result,
+                self.previous_linear_output,
+                params.linear_slew.unwrap_or(0.0),
+                None,
+            )
+        };
+        self.previous_linear_output = linear_output;
+
+        // The turn rate that follows the arc through the lookahead point,
+        // as the difference from the linear output of each side.
+        let angular_output = linear_output.abs() * curvature * settings.track_width / 2.0;
+        let (left, right) = arcade_desaturate(
+            if params.forwards {
+                linear_output
+            } else {
+                -linear_output
+            },
+            angular_output,
+        );
+        log_telemetry(
+            "follow_path",
+            &[("linear", settings.linear_controller.as_ref())],
+        );
+        info!(
+            "follow_path: progress: {}, remaining: {}, lookahead: {}, curvature: {}, l. output: {}, left: {}, right: {}",
+            progress, remaining_distance, lookahead, curvature, linear_output, left, right
+        );
+        MotionOutput::velocity(left, right)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.exit_reason.is_some()
+    }
+
+    fn exit_reason(&self) -> MotionExitReason {
+        self.exit_reason.unwrap_or(MotionExitReason::Settled)
+    }
+
+    fn linear_error(&self) -> Option<f64> {
+        self.linear_error
+    }
+
+    fn exit_velocity(&self) -> (f64, f64) {
+        let linear = if self.params.forwards {
+            self.previous_linear_output
+        } else {
+            -self.previous_linear_output
+        };
+        (linear, 0.0)
+    }
+
+    fn seed_velocity(&mut self, linear: f64, _angular: f64) {
+        self.previous_linear_output = if self.params.forwards {
+            linear
+        } else {
+            -linear
+        };
+    }
+
+    fn pass_through(&mut self, min_speed: f64, exit_range: f64) {
+        self.params.min_linear_speed = self.params.min_linear_speed.max(min_speed);
+        self.params.early_exit_range = self.params.early_exit_range.max(exit_range);
+        self.is_passing_through = true;
+    }
+}
+
+#[bon]
+impl<T: Tracking + 'static> Chassis<T> {
+    /// Follows a path with a [`PurePursuitMotion`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// chassis
+    ///     .clone()
+    ///     .follow_path()
+    ///     .path(vec![(-48.0, -24.0), (-24.0, -24.0, 0.5), (0.0, -48.0)])
+    ///     .params(params_pure_pursuit!(max_lookahead: 12.0))
+    ///     .call()
+    ///     .await;
+    /// ```
+    #[builder]
+    pub async fn follow_path(
+        self: Rc<Self>,
+        path: impl Into<WaypointPath> + 'static,
+        timeout: Option<Duration>,
+        params: Option<PurePursuitParameters>,
+        settings: Option<PurePursuitSettings>,
+        priority: Option<MotionPriority>,
+        run_async: Option<bool>,
+    ) -> MotionHandle {
+        info!("Following path!");
+        let motion = PurePursuitMotion::new(
+            path,
+            params.unwrap_or(params_pure_pursuit!()),
+            self.settings_source(settings, |settings| &settings.pure_pursuit_settings),
+        );
+        self.run_motion()
+            .motion(motion)
+            .maybe_timeout(timeout)
+            .maybe_priority(priority)
+            .maybe_run_async(run_async)
+            .call()
+            .await
+    }
+}
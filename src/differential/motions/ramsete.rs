@@ -4,17 +4,17 @@ use core::{f64::consts::PI, time::Duration};
 use bon::{bon, Builder};
 use nalgebra::{Vector2, Vector3};
 use num_traits::{AsPrimitive, Num};
-use vexide::{float::Float, prelude::Motor};
+use vexide::float::Float;
 
-use super::ToleranceGroup;
+use super::{
+    log_telemetry, Motion, MotionExitReason, MotionHandle, MotionOutput, MotionPriority,
+    SettingsSource, ToleranceGroup,
+};
 use crate::{
-    controllers::FeedbackController,
+    controllers::{FeedbackController, PoseTrackingController},
     differential::{chassis::Chassis, pose::Pose},
     tracking::Tracking,
-    utils::{
-        math::{angle_error, arcade_desaturate, delta_clamp},
-        timer::Timer,
-    },
+    utils::math::{angle_error, arcade_desaturate, delta_clamp},
 };
 
 #[derive(Clone, Copy, PartialEq, Builder)]
@@ -34,6 +34,7 @@ pub struct RAMSETEHybridParameters {
     #[builder(default = 1.0)]
     pub max_angular_speed: f64,
 
+    /// Exits once the robot is within this distance of the target, in inches.
     #[builder(default = 0.0)]
     pub early_exit_range: f64,
     pub linear_slew: Option<f64>,
@@ -51,6 +52,11 @@ pub struct RAMSETEHybridSettings {
     angular_tolerances: ToleranceGroup<f64>,
 
     b: f64,
+
+    /// If set, replaces the angular controller and the RAMSETE term, such as with a
+    /// [`crate::controllers::lqr::UnicycleLQR`]. It is given the velocity from the
+    /// linear controller as the reference linear velocity.
+    tracking_controller: Option<Box<dyn PoseTrackingController>>,
 }
 
 impl RAMSETEHybridSettings {
@@ -67,14 +73,26 @@ impl RAMSETEHybridSettings {
             linear_tolerances,
             angular_tolerances,
             b,
+            tracking_controller: None,
         }
     }
 
+    pub fn with_tracking_controller(
+        mut self,
+        tracking_controller: Box<dyn PoseTrackingController>,
+    ) -> Self {
+        self.tracking_controller = Some(tracking_controller);
+        self
+    }
+
     pub fn reset(&mut self) {
         self.linear_controller.reset();
         self.angular_controller.reset();
         self.linear_tolerances.reset();
         self.angular_tolerances.reset();
+        if let Some(tracking_controller) = &mut self.tracking_controller {
+            tracking_controller.reset();
+        }
     }
 }
 
@@ -128,227 +146,248 @@ impl<T: AsPrimitive<f64>, U: AsPrimitive<f64>, V: AsPrimitive<f64>> From<(T, U,
     }
 }
 
-#[bon]
-impl<T: Tracking + 'static> Chassis<T> {
-    #[builder]
-    pub async fn ramsete_hybrid(
-        self: Rc<Self>,
-        target: impl Into<RAMSETETarget> + 'static,
-        timeout: Option<Duration>,
-        params: Option<RAMSETEHybridParameters>,
-        mut settings: Option<RAMSETEHybridSettings>,
-        run_async: Option<bool>,
-    ) {
-        self.motion_handler.wait_for_motions_end().await;
-        if !self.motion_handler.is_in_motion() {
-            return;
+/// Drives to a point or pose with the RAMSETE hybrid control scheme above.
+pub struct RAMSETEHybridMotion {
+    target: RAMSETETarget,
+    params: RAMSETEHybridParameters,
+    settings: SettingsSource<RAMSETEHybridSettings>,
+    is_near: bool, // Possibly use settling logic.
+    previous_linear_output: f64,
+    previous_angular_output: f64,
+    linear_error: Option<f64>,
+    angular_error: Option<f64>,
+    exit_reason: Option<MotionExitReason>,
+}
+
+impl RAMSETEHybridMotion {
+    /// # Panics
+    ///
+    /// Panics if the minimum linear speed exceeds the maximum.
+    pub fn new(
+        target: impl Into<RAMSETETarget>,
+        mut params: RAMSETEHybridParameters,
+        settings: impl Into<SettingsSource<RAMSETEHybridSettings>>,
+    ) -> Self {
+        params.min_linear_speed = params.min_linear_speed.abs();
+        params.max_linear_speed = params.max_linear_speed.abs();
+        params.max_angular_speed = params.max_angular_speed.abs();
+        assert!(
+            params.max_linear_speed >= params.min_linear_speed,
+            "Minimum speed may not exceed the maximum."
+        );
+        Self {
+            target: target.into(),
+            params,
+            settings: settings.into(),
+            is_near: false,
+            previous_linear_output: 0.0,
+            previous_angular_output: 0.0,
+            linear_error: None,
+            angular_error: None,
+            exit_reason: None,
         }
-        if run_async.unwrap_or(true) {
-            // Spawn vexide task
-            vexide::task::spawn({
-                let self_clone = self.clone();
-                async move {
-                    self_clone
-                        .ramsete_hybrid()
-                        .target(target)
-                        .maybe_timeout(timeout)
-                        .maybe_params(params)
-                        .maybe_settings(settings.clone())
-                        .run_async(false)
-                        .call()
-                        .await
-                }
-            })
-            .detach();
-            self.motion_handler.end_motion().await;
-            vexide::time::sleep(Duration::from_millis(10)).await;
-            return;
+    }
+}
+
+impl Motion for RAMSETEHybridMotion {
+    fn init(&mut self, _pose: Pose) {
+        self.settings.resolve().reset();
+    }
+
+    fn step(&mut self, pose: Pose) -> MotionOutput {
+        let settings = self.settings.resolve();
+        let mut local_error = Pose::from(
+            // Similar to ∇×v, by the right hand rule, a negative direction
+            // value is a clockwise rotation (negative orientation).
+            nalgebra::Rotation3::new(Vector3::<f64>::new(0.0, 0.0, -pose.orientation))
+                * <Pose as Into<Vector3<f64>>>::into(
+                    match self.target {
+                        RAMSETETarget::Point(target_point) => {
+                            Pose::new(target_point.x, target_point.y, {
+                                let error = target_point - pose.position;
+                                error.y.atan2(error.x)
+                            })
+                        }
+                        RAMSETETarget::Pose(target_pose) => target_pose,
+                    } - pose,
+                ),
+        );
+        if !self.is_near && local_error.position.norm() < 5.0 {
+            self.is_near = true;
+            self.params.max_linear_speed = self.previous_linear_output.max(0.5);
+        }
+        local_error.orientation = angle_error(
+            local_error.orientation,
+            if self.params.forwards { 0.0 } else { PI },
+            true,
+            None,
+        );
+
+        self.linear_error = Some(local_error.position.norm());
+        self.angular_error = Some(local_error.orientation);
+        if local_error.position.norm() < self.params.early_exit_range {
+            self.exit_reason = Some(MotionExitReason::EarlyExit);
+            return MotionOutput::velocity(0.0, 0.0);
         }
-        if let Some(settings) = &mut settings {
-            settings.reset();
+        let linear_done = settings
+            .linear_tolerances
+            .update_all(local_error.position.norm());
+        let angular_done = if settings
+            .angular_tolerances
+            .update_all(local_error.orientation)
+        {
+            true
         } else {
-            self.motion_settings
-                .ramsete_hybrid_settings
-                .borrow_mut()
-                .reset();
+            matches!(self.target, RAMSETETarget::Point(_)) // Point-based RAMSETE does not check angular tolerances.
+        };
+        if linear_done && angular_done && self.is_near {
+            self.exit_reason = Some(MotionExitReason::Settled);
+            return MotionOutput::velocity(0.0, 0.0);
         }
-        *self.distance_traveled.borrow_mut() = Some(0.0);
-        let target: RAMSETETarget = target.into();
-        let mut unwrapped_params = params.unwrap_or(params_ramsete_h!());
-        unwrapped_params.min_linear_speed = unwrapped_params.min_linear_speed.abs();
-        unwrapped_params.max_linear_speed = unwrapped_params.max_linear_speed.abs();
-        unwrapped_params.max_angular_speed = unwrapped_params.max_angular_speed.abs();
-        assert!(
-            unwrapped_params.max_angular_speed >= unwrapped_params.min_linear_speed,
-            "Minimum speed may not exceed the maximum."
+
+        let v_d = settings.linear_controller.update(
+            local_error.position.norm() * local_error.orientation.cos().signum(),
+            0.0,
         );
-        let mut previous_pose = self.pose().await;
-        let mut is_near = false; // Possibly use settling logic.
-        let mut previous_linear_output: f64 = 0.0;
-        let mut previous_angular_output: f64 = 0.0;
-        let mut timer = Timer::new(timeout.unwrap_or(Duration::MAX));
-        while !timer.is_done() && self.motion_handler.is_in_motion() {
-            let pose: Pose = self.pose().await;
-            if let Some(distance_traveled) = self.distance_traveled.borrow_mut().as_mut() {
-                *distance_traveled += pose.distance_to(&previous_pose);
-            }
-            previous_pose = pose;
-            let mut local_error = Pose::from(
-                // Similar to ∇×v, by the right hand rule, a negative direction
-                // value is a clockwise rotation (negative orientation).
-                nalgebra::Rotation3::new(Vector3::<f64>::new(0.0, 0.0, -pose.orientation))
-                    * <Pose as Into<Vector3<f64>>>::into(
-                        match target {
-                            RAMSETETarget::Point(target_point) => {
-                                Pose::new(target_point.x, target_point.y, {
-                                    let error = target_point - pose.position;
-                                    error.y.atan2(error.x)
-                                })
-                            }
-                            RAMSETETarget::Pose(target_pose) => target_pose,
-                        } - pose,
-                    ),
-            );
-            if !is_near && local_error.position.norm() < 5.0 {
-                is_near = true;
-                unwrapped_params.max_linear_speed = previous_linear_output.max(0.5);
-            }
-            local_error.orientation = angle_error(
-                local_error.orientation,
-                if unwrapped_params.forwards { 0.0 } else { PI },
-                true,
-                None,
-            );
-
-            if if let Some(settings) = &mut settings {
-                let linear_done = settings
-                    .linear_tolerances
-                    .update_all(local_error.position.norm());
-                let angular_done = if settings
-                    .angular_tolerances
-                    .update_all(local_error.orientation)
-                {
-                    true
-                } else {
-                    matches!(target, RAMSETETarget::Point(_)) // Point-based RAMSETE does not check angular tolerances.
-                };
-                linear_done && angular_done
-            } else {
-                let mut motion_settings = self.motion_settings.boomerang_settings.borrow_mut();
-                let linear_done = motion_settings
-                    .linear_tolerances
-                    .update_all(local_error.position.norm());
-                let angular_done = if motion_settings
-                    .angular_tolerances
-                    .update_all(local_error.orientation)
-                {
-                    true
-                } else {
-                    matches!(target, RAMSETETarget::Point(_)) // Point-based RAMSETE does not check angular tolerances.
-                };
-                linear_done && angular_done
-            } && is_near
+        let linear_output = (v_d * local_error.orientation.cos().abs())
+            .clamp(-self.params.max_linear_speed, self.params.max_linear_speed);
+        let linear_output = {
+            let check_1_result = if (-self.params.min_linear_speed..self.params.min_linear_speed)
+                .contains(&linear_output)
             {
-                break;
-            }
-
-            let v_d = {
-                let controller_input =
-                    local_error.position.norm() * local_error.orientation.cos().signum();
-                if let Some(settings) = &mut settings {
-                    settings.linear_controller.update(controller_input, 0.0)
-                } else {
-                    self.motion_settings
-                        .ramsete_hybrid_settings
-                        .borrow_mut()
-                        .linear_controller
-                        .update(controller_input, 0.0)
-                }
+                self.params.min_linear_speed
+            } else {
+                linear_output
             };
-            let linear_output = (v_d * local_error.orientation.cos().abs()).clamp(
-                -unwrapped_params.max_linear_speed,
-                unwrapped_params.max_linear_speed,
-            );
-            let linear_output = {
-                let check_1_result = if (-unwrapped_params.min_linear_speed
-                    ..unwrapped_params.min_linear_speed)
-                    .contains(&linear_output)
-                {
-                    unwrapped_params.min_linear_speed
-                } else {
-                    linear_output
-                };
-                let check_2_result = if check_1_result < 0.0 && !is_near {
-                    0.0
-                } else {
-                    check_1_result
-                };
-                delta_clamp(
-                    check_2_result,
-                    previous_linear_output,
-                    unwrapped_params.linear_slew.unwrap_or(0.0),
-                    None,
-                )
+            let check_2_result = if check_1_result < 0.0 && !self.is_near {
+                0.0
+            } else {
+                check_1_result
             };
-            let angular_output = ({
-                if let Some(settings) = &mut settings {
-                    settings.angular_controller.update(local_error.orientation, 0.0)
-                } else {
-                    self.motion_settings
-                        .ramsete_hybrid_settings
-                        .borrow_mut()
-                        .angular_controller
-                        .update(local_error.orientation, 0.0)
-                }
-            } + v_d
+            delta_clamp(
+                check_2_result,
+                self.previous_linear_output,
+                self.params.linear_slew.unwrap_or(0.0),
+                None,
+            )
+        };
+        let tracking_output = {
+            // The tracking controller works in the frame of the direction of travel.
+            let mut tracking_error = local_error;
+            if !self.params.forwards {
+                tracking_error.position = -tracking_error.position;
+            }
+            settings
+                .tracking_controller
+                .as_mut()
+                .map(|tracking_controller| {
+                    tracking_controller.calculate(tracking_error, v_d, 0.0).1
+                })
+        };
+        let angular_output = settings
+            .angular_controller
+            .update(local_error.orientation, 0.0)
+            + v_d
                 * local_error.position.y
                 * {
                     use nalgebra::ComplexField;
                     local_error.orientation.sinc()
                 }
-                * {
-                    unwrapped_params
-                        .b
-                        .unwrap_or(if let Some(settings) = &settings {
-                            settings.b
-                        } else {
-                            self.motion_settings.ramsete_hybrid_settings.borrow().b
-                        })
-                })
-            .clamp(
-                -unwrapped_params.max_angular_speed,
-                unwrapped_params.max_angular_speed,
-            );
-            let angular_output = delta_clamp(
-                angular_output,
-                previous_angular_output,
-                unwrapped_params.angular_slew.unwrap_or(0.0),
-                None,
-            );
-            previous_linear_output = linear_output;
-            previous_angular_output = angular_output;
-
-            let (left, right) = arcade_desaturate(
-                if unwrapped_params.forwards {
-                    linear_output
-                } else {
-                    -linear_output
-                },
-                angular_output,
-            );
-
-            self.drivetrain
-                .left_motors
-                .borrow_mut()
-                .set_velocity_percentage_all(left);
-            self.drivetrain
-                .right_motors
-                .borrow_mut()
-                .set_velocity_percentage_all(right);
-
-            vexide::time::sleep(Motor::WRITE_INTERVAL).await;
-        }
-        *self.distance_traveled.borrow_mut() = None;
-        self.motion_handler.end_motion().await;
+                * self.params.b.unwrap_or(settings.b);
+        let angular_output = tracking_output.unwrap_or(angular_output).clamp(
+            -self.params.max_angular_speed,
+            self.params.max_angular_speed,
+        );
+        let angular_output = delta_clamp(
+            angular_output,
+            self.previous_angular_output,
+            self.params.angular_slew.unwrap_or(0.0),
+            None,
+        );
+        self.previous_linear_output = linear_output;
+        self.previous_angular_output = angular_output;
+
+        log_telemetry(
+            "ramsete_hybrid",
+            &[
+                ("linear", settings.linear_controller.as_ref()),
+                ("angular", settings.angular_controller.as_ref()),
+            ],
+        );
+        let (left, right) = arcade_desaturate(
+            if self.params.forwards {
+                linear_output
+            } else {
+                -linear_output
+            },
+            angular_output,
+        );
+        MotionOutput::velocity(left, right)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.exit_reason.is_some()
+    }
+
+    fn exit_reason(&self) -> MotionExitReason {
+        self.exit_reason.unwrap_or(MotionExitReason::Settled)
+    }
+
+    fn linear_error(&self) -> Option<f64> {
+        self.linear_error
+    }
+
+    fn angular_error(&self) -> Option<f64> {
+        self.angular_error
+    }
+
+    fn exit_velocity(&self) -> (f64, f64) {
+        let linear = if self.params.forwards {
+            self.previous_linear_output
+        } else {
+            -self.previous_linear_output
+        };
+        (linear, self.previous_angular_output)
+    }
+
+    fn seed_velocity(&mut self, linear: f64, angular: f64) {
+        self.previous_linear_output = if self.params.forwards {
+            linear
+        } else {
+            -linear
+        };
+        self.previous_angular_output = angular;
+    }
+
+    fn pass_through(&mut self, min_speed: f64, exit_range: f64) {
+        self.params.min_linear_speed = self.params.min_linear_speed.max(min_speed);
+        self.params.early_exit_range = self.params.early_exit_range.max(exit_range);
+    }
+}
+
+#[bon]
+impl<T: Tracking + 'static> Chassis<T> {
+    #[builder]
+    pub async fn ramsete_hybrid(
+        self: Rc<Self>,
+        target: impl Into<RAMSETETarget> + 'static,
+        timeout: Option<Duration>,
+        params: Option<RAMSETEHybridParameters>,
+        settings: Option<RAMSETEHybridSettings>,
+        priority: Option<MotionPriority>,
+        run_async: Option<bool>,
+    ) -> MotionHandle {
+        let motion = RAMSETEHybridMotion::new(
+            target,
+            params.unwrap_or(params_ramsete_h!()),
+            self.settings_source(settings, |settings| &settings.ramsete_hybrid_settings),
+        );
+        self.run_motion()
+            .motion(motion)
+            .maybe_timeout(timeout)
+            .maybe_priority(priority)
+            .maybe_run_async(run_async)
+            .call()
+            .await
     }
 }
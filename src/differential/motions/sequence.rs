@@ -0,0 +1,296 @@
+use alloc::{boxed::Box, collections::VecDeque, rc::Rc, vec::Vec};
+use core::time::Duration;
+
+use log::debug;
+
+use super::{
+    angular::{TurnToMotion, TurnToParameters, TurnToTarget},
+    boomerang::{BoomerangMotion, BoomerangParameters, MoveToPoseTarget},
+    linear::{MoveToPointMotion, MoveToPointParameters, MoveToPointTarget},
+    pure_pursuit::{PurePursuitMotion, PurePursuitParameters},
+    ramsete::{RAMSETEHybridMotion, RAMSETEHybridParameters, RAMSETETarget},
+    Motion, MotionExitReason, MotionHandle, MotionOutput, MotionPriority,
+};
+use crate::{
+    differential::{chassis::Chassis, path::waypoint::WaypointPath, pose::Pose},
+    tracking::Tracking,
+};
+
+/// Runs motions one after another as a single motion. Every segment but the last
+/// passes through its target instead of settling, and each segment continues from
+/// the exit velocity of the one before it.
+pub struct MotionSequence {
+    segments: VecDeque<Box<dyn Motion>>,
+}
+
+impl MotionSequence {
+    /// # Panics
+    ///
+    /// Panics if there are no segments.
+    pub fn new(segments: Vec<Box<dyn Motion>>, min_speed: f64, exit_range: f64) -> Self {
+        assert!(!segments.is_empty(), "A motion sequence needs a segment.");
+        let mut segments = VecDeque::from(segments);
+        let last = segments.len() - 1;
+        for segment in segments.range_mut(..last) {
+            segment.pass_through(min_speed, exit_range);
+        }
+        Self { segments }
+    }
+
+    /// The number of segments left, including the running one.
+    pub fn remaining_segments(&self) -> usize {
+        self.segments.len()
+    }
+}
+
+impl Motion for MotionSequence {
+    fn init(&mut self, pose: Pose) {
+        self.segments[0].init(pose);
+    }
+
+    fn step(&mut self, pose: Pose) -> MotionOutput {
+        loop {
+            let output = self.segments[0].step(pose);
+            if !self.segments[0].is_finished() || self.segments.len() == 1 {
+                return output;
+            }
+            // Hand off to the next segment within the same step, so that the
+            // drivetrain never sees a stop between them.
+            let (linear, angular) = self.segments[0].exit_velocity();
+            self.segments.pop_front();
+            debug!(
+                "Motion sequence: next segment, {} left, linear: {}, angular: {}",
+                self.segments.len(),
+                linear,
+                angular
+            );
+            let next = &mut self.segments[0];
+            next.init(pose);
+            next.seed_velocity(linear, angular);
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.segments.len() == 1 && self.segments[0].is_finished()
+    }
+
+    fn exit_reason(&self) -> MotionExitReason {
+        self.segments[0].exit_reason()
+    }
+
+    fn on_exit(&mut self) -> Option<MotionOutput> {
+        self.segments[0].on_exit()
+    }
+
+    fn linear_error(&self) -> Option<f64> {
+        self.segments[0].linear_error()
+    }
+
+    fn angular_error(&self) -> Option<f64> {
+        self.segments[0].angular_error()
+    }
+
+    /// The progress of the running segment. The distance traveled over a sequence
+    /// therefore adds the radians of its turn segments to the inches of the rest.
+    fn distance_between(&self, previous_pose: Pose, pose: Pose) -> f64 {
+        self.segments[0].distance_between(previous_pose, pose)
+    }
+
+    fn exit_velocity(&self) -> (f64, f64) {
+        self.segments[0].exit_velocity()
+    }
+
+    fn seed_velocity(&mut self, linear: f64, angular: f64) {
+        self.segments[0].seed_velocity(linear, angular);
+    }
+
+    fn pass_through(&mut self, min_speed: f64, exit_range: f64) {
+        for segment in self.segments.iter_mut() {
+            segment.pass_through(min_speed, exit_range);
+        }
+    }
+}
+
+/// Builds a [`MotionSequence`] from the default settings of a chassis. See
+/// [`Chassis::motion_sequence`].
+pub struct MotionSequenceBuilder<T: Tracking> {
+    chassis: Rc<Chassis<T>>,
+    segments: Vec<Box<dyn Motion>>,
+    min_speed: f64,
+    exit_range: f64,
+    timeout: Option<Duration>,
+    priority: Option<MotionPriority>,
+    run_async: Option<bool>,
+}
+
+impl<T: Tracking + 'static> MotionSequenceBuilder<T> {
+    /// The minimum speed for the segments that pass through their targets.
+    pub const DEFAULT_MIN_SPEED: f64 = 0.5;
+
+    /// The distance in inches from their targets at which the segments that pass
+    /// through them exit.
+    pub const DEFAULT_EXIT_RANGE: f64 = 4.0;
+
+    fn new(chassis: Rc<Chassis<T>>) -> Self {
+        Self {
+            chassis,
+            segments: Vec::new(),
+            min_speed: Self::DEFAULT_MIN_SPEED,
+            exit_range: Self::DEFAULT_EXIT_RANGE,
+            timeout: None,
+            priority: None,
+            run_async: None,
+        }
+    }
+
+    pub fn turn_to(
+        mut self,
+        target: impl Into<TurnToTarget>,
+        params: impl Into<Option<TurnToParameters>>,
+    ) -> Self {
+        let settings = self
+            .chassis
+            .settings_source(None, |settings| &settings.turn_to_settings);
+        self.segments.push(Box::new(TurnToMotion::new(
+            target,
+            params.into().unwrap_or(params_turn_to!()),
+            settings,
+        )));
+        self
+    }
+
+    pub fn move_to_point(
+        mut self,
+        target: impl Into<MoveToPointTarget>,
+        params: impl Into<Option<MoveToPointParameters>>,
+    ) -> Self {
+        let settings = self
+            .chassis
+            .settings_source(None, |settings| &settings.move_to_point_settings);
+        self.segments.push(Box::new(MoveToPointMotion::new(
+            target,
+            params.into().unwrap_or(params_move_to_point!()),
+            settings,
+        )));
+        self
+    }
+
+    pub fn boomerang(
+        mut self,
+        target: impl Into<MoveToPoseTarget>,
+        params: impl Into<Option<BoomerangParameters>>,
+    ) -> Self {
+        let settings = self
+            .chassis
+            .settings_source(None, |settings| &settings.boomerang_settings);
+        self.segments.push(Box::new(BoomerangMotion::new(
+            target,
+            params.into().unwrap_or(params_boomerang!()),
+            settings,
+        )));
+        self
+    }
+
+    pub fn ramsete_hybrid(
+        mut self,
+        target: impl Into<RAMSETETarget>,
+        params: impl Into<Option<RAMSETEHybridParameters>>,
+    ) -> Self {
+        let settings = self
+            .chassis
+            .settings_source(None, |settings| &settings.ramsete_hybrid_settings);
+        self.segments.push(Box::new(RAMSETEHybridMotion::new(
+            target,
+            params.into().unwrap_or(params_ramsete_h!()),
+            settings,
+        )));
+        self
+    }
+
+    pub fn follow_path(
+        mut self,
+        path: impl Into<WaypointPath>,
+        params: impl Into<Option<PurePursuitParameters>>,
+    ) -> Self {
+        let settings = self
+            .chassis
+            .settings_source(None, |settings| &settings.pure_pursuit_settings);
+        self.segments.push(Box::new(PurePursuitMotion::new(
+            path,
+            params.into().unwrap_or(params_pure_pursuit!()),
+            settings,
+        )));
+        self
+    }
+
+    /// Adds any motion as a segment, such as one with its own settings.
+    pub fn motion(mut self, motion: impl Motion + 'static) -> Self {
+        self.segments.push(Box::new(motion));
+        self
+    }
+
+    pub fn min_speed(mut self, min_speed: f64) -> Self {
+        self.min_speed = min_speed;
+        self
+    }
+
+    pub fn exit_range(mut self, exit_range: f64) -> Self {
+        self.exit_range = exit_range;
+        self
+    }
+
+    /// The timeout for the whole sequence.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn priority(mut self, priority: MotionPriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    pub fn run_async(mut self, run_async: bool) -> Self {
+        self.run_async = Some(run_async);
+        self
+    }
+
+    /// Runs the sequence with [`Chassis::run_motion`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if no segments were added.
+    pub async fn call(self) -> MotionHandle {
+        let sequence = MotionSequence::new(self.segments, self.min_speed, self.exit_range);
+        self.chassis
+            .run_motion()
+            .motion(sequence)
+            .maybe_timeout(self.timeout)
+            .maybe_priority(self.priority)
+            .maybe_run_async(self.run_async)
+            .call()
+            .await
+    }
+}
+
+impl<T: Tracking + 'static> Chassis<T> {
+    /// Chains motions into one, passing through the targets of all but the last
+    /// without stopping.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// chassis
+    ///     .clone()
+    ///     .motion_sequence()
+    ///     .turn_to((-24.0, -24.0), None)
+    ///     .move_to_point((-24.0, -24.0), None)
+    ///     .boomerang((0.0, -48.0, 90.0.hdg_deg()), params_boomerang!(lead: 0.4))
+    ///     .timeout(Duration::from_secs(4))
+    ///     .call()
+    ///     .await;
+    /// ```
+    pub fn motion_sequence(self: Rc<Self>) -> MotionSequenceBuilder<T> {
+        MotionSequenceBuilder::new(self)
+    }
+}
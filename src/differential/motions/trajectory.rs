@@ -0,0 +1,209 @@
+use alloc::{boxed::Box, rc::Rc};
+use core::time::Duration;
+
+use bon::bon;
+use log::info;
+
+use super::{Motion, MotionExitReason, MotionHandle, MotionOutput, MotionPriority, SettingsSource};
+use crate::{
+    controllers::PoseTrackingController,
+    differential::{
+        chassis::Chassis, kinematics::DifferentialDriveKinematics, pose::Pose,
+        trajectory::Trajectory,
+    },
+    tracking::Tracking,
+    utils::{
+        clock::{vex_clock, Clock},
+        math::angle_error,
+    },
+};
+
+#[derive(Clone)]
+pub struct FollowTrajectorySettings {
+    /// Corrects for the error from the trajectory, such as a
+    /// [`crate::controllers::ramsete::RAMSETE`].
+    tracking_controller: Box<dyn PoseTrackingController>,
+    kinematics: DifferentialDriveKinematics,
+
+    /// The velocity of the wheels at full speed in inches per second.
+    max_wheel_velocity: f64,
+}
+
+impl FollowTrajectorySettings {
+    pub fn new(
+        tracking_controller: Box<dyn PoseTrackingController>,
+        kinematics: DifferentialDriveKinematics,
+        max_wheel_velocity: f64,
+    ) -> Self {
+        assert!(
+            max_wheel_velocity > 0.0,
+            "The maximum wheel velocity must be positive."
+        );
+        Self {
+            tracking_controller,
+            kinematics,
+            max_wheel_velocity,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.tracking_controller.reset();
+    }
+
+    /// The wheel speeds as percentages for a linear and angular velocity, scaled down
+    /// together if either exceeds full speed.
+    fn wheel_percentages(&self, linear_velocity: f64, angular_velocity: f64) -> (f64, f64) {
+        let (left, right) = self
+            .kinematics
+            .to_wheel_velocities(linear_velocity, angular_velocity);
+        let scale = self.max_wheel_velocity.max(left.abs()).max(right.abs());
+        (left / scale, right / scale)
+    }
+}
+
+/// Follows a trajectory in time, with the tracking controller correcting for the
+/// error from the reference at each step. It ends at the end of the trajectory
+/// and drives at its final velocity.
+pub struct FollowTrajectoryMotion {
+    trajectory: Trajectory,
+    settings: SettingsSource<FollowTrajectorySettings>,
+    start_time: Option<Duration>,
+    clock: Rc<dyn Clock>,
+    linear_error: Option<f64>,
+    angular_error: Option<f64>,
+    exit_reason: Option<MotionExitReason>,
+}
+
+impl FollowTrajectoryMotion {
+    pub fn new(
+        trajectory: Trajectory,
+        settings: impl Into<SettingsSource<FollowTrajectorySettings>>,
+    ) -> Self {
+        Self {
+            trajectory,
+            settings: settings.into(),
+            start_time: None,
+            clock: vex_clock(),
+            linear_error: None,
+            angular_error: None,
+            exit_reason: None,
+        }
+    }
+
+    /// Samples the trajectory by the time of `clock`, such as a
+    /// [`crate::utils::clock::MockClock`].
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+impl Motion for FollowTrajectoryMotion {
+    fn init(&mut self, _pose: Pose) {
+        self.settings.resolve().reset();
+        self.start_time = Some(self.clock.now());
+    }
+
+    fn step(&mut self, pose: Pose) -> MotionOutput {
+        let settings = self.settings.resolve();
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(*self.start_time.get_or_insert(now));
+        if elapsed > self.trajectory.duration() {
+            self.exit_reason = Some(MotionExitReason::Settled);
+            return MotionOutput::velocity(0.0, 0.0);
+        }
+        let reference = self.trajectory.sample(elapsed);
+
+        let local_error = Pose {
+            position: nalgebra::Rotation2::new(-pose.orientation)
+                * (reference.pose.position - pose.position),
+            orientation: angle_error(reference.pose.orientation, pose.orientation, true, None),
+        };
+        self.linear_error = Some(local_error.position.norm());
+        self.angular_error = Some(local_error.orientation);
+        let (linear_velocity, angular_velocity) = settings.tracking_controller.calculate(
+            local_error,
+            reference.linear_velocity,
+            reference.angular_velocity,
+        );
+        let (left, right) = settings.wheel_percentages(linear_velocity, angular_velocity);
+        info!(
+            "follow_trajectory: time: {:?}, error: {}, left: {}, right: {}",
+            elapsed, local_error, left, right
+        );
+        MotionOutput::velocity(left, right)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.exit_reason.is_some()
+    }
+
+    fn exit_reason(&self) -> MotionExitReason {
+        self.exit_reason.unwrap_or(MotionExitReason::Settled)
+    }
+
+    fn linear_error(&self) -> Option<f64> {
+        self.linear_error
+    }
+
+    fn angular_error(&self) -> Option<f64> {
+        self.angular_error
+    }
+
+    /// The motion has no exit velocity until it has started.
+    fn exit_velocity(&self) -> (f64, f64) {
+        let Some(settings) = self.settings.resolved() else {
+            return (0.0, 0.0);
+        };
+        let final_state = self.trajectory.final_state();
+        let (left, right) =
+            settings.wheel_percentages(final_state.linear_velocity, final_state.angular_velocity);
+        ((left + right) / 2.0, (right - left) / 2.0)
+    }
+
+    fn on_exit(&mut self) -> Option<MotionOutput> {
+        let settings = self.settings.resolve();
+        let final_state = self.trajectory.final_state();
+        let (left, right) =
+            settings.wheel_percentages(final_state.linear_velocity, final_state.angular_velocity);
+        Some(MotionOutput::velocity(left, right))
+    }
+}
+
+#[bon]
+impl<T: Tracking + 'static> Chassis<T> {
+    /// Follows a trajectory with a [`FollowTrajectoryMotion`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let trajectory = Trajectory::from_poses(
+    ///     &[Pose::new(-48, -24, 0), Pose::new(0, 0, 90.0.hdg_deg())],
+    ///     params_trajectory!(max_velocity: 60.0, max_acceleration: 120.0),
+    /// );
+    /// chassis.set_pose(trajectory.initial_state().pose).await;
+    /// chassis.clone().follow_trajectory().trajectory(trajectory).call().await;
+    /// ```
+    #[builder]
+    pub async fn follow_trajectory(
+        self: Rc<Self>,
+        trajectory: Trajectory,
+        timeout: Option<Duration>,
+        settings: Option<FollowTrajectorySettings>,
+        priority: Option<MotionPriority>,
+        run_async: Option<bool>,
+    ) -> MotionHandle {
+        info!("Following trajectory!");
+        let motion = FollowTrajectoryMotion::new(
+            trajectory,
+            self.settings_source(settings, |settings| &settings.follow_trajectory_settings),
+        );
+        self.run_motion()
+            .motion(motion)
+            .maybe_timeout(timeout)
+            .maybe_priority(priority)
+            .maybe_run_async(run_async)
+            .call()
+            .await
+    }
+}
@@ -0,0 +1,247 @@
+use alloc::{boxed::Box, rc::Rc, vec::Vec};
+use core::{
+    cell::RefCell,
+    f64::consts::FRAC_PI_2,
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use nalgebra::Vector2;
+use num_traits::AsPrimitive;
+
+use super::MotionHandle;
+use crate::{differential::pose::Pose, utils::math::angle_error};
+
+/// When a trigger registered on a [`MotionHandle`] fires.
+pub enum TriggerCondition {
+    /// After the motion has traveled a distance, as measured by
+    /// [`super::Motion::distance_between`]. This is in radians for turns.
+    Distance(f64),
+
+    /// After the motion has run for a duration.
+    Elapsed(Duration),
+
+    /// On entering a circle on the field, in inches.
+    Circle { center: Vector2<f64>, radius: f64 },
+
+    /// On entering a polygon on the field, with its vertices in order.
+    Polygon(Vec<Vector2<f64>>),
+
+    /// On crossing a heading, in radians.
+    Heading(f64),
+
+    /// Once the predicate is true for the pose.
+    Predicate(Box<dyn FnMut(Pose) -> bool>),
+}
+
+impl TriggerCondition {
+    pub fn circle<T: AsPrimitive<f64>, U: AsPrimitive<f64>>(x: T, y: U, radius: f64) -> Self {
+        Self::Circle {
+            center: Vector2::new(x.as_(), y.as_()),
+            radius,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if there are fewer than three vertices.
+    pub fn polygon<T: AsPrimitive<f64>, U: AsPrimitive<f64>>(
+        vertices: impl IntoIterator<Item = (T, U)>,
+    ) -> Self {
+        let vertices = vertices
+            .into_iter()
+            .map(|(x, y)| Vector2::new(x.as_(), y.as_()))
+            .collect::<Vec<_>>();
+        assert!(
+            vertices.len() >= 3,
+            "A polygon needs at least three vertices."
+        );
+        Self::Polygon(vertices)
+    }
+
+    pub fn predicate(predicate: impl FnMut(Pose) -> bool + 'static) -> Self {
+        Self::Predicate(Box::new(predicate))
+    }
+}
+
+/// Whether a point is inside a polygon, by counting the edges that a ray from
+/// it crosses.
+fn is_inside_polygon(point: Vector2<f64>, vertices: &[Vector2<f64>]) -> bool {
+    let mut is_inside = false;
+    let mut previous = vertices[vertices.len() - 1];
+    for &vertex in vertices {
+        if (vertex.y > point.y) != (previous.y > point.y)
+            && point.x
+                < (previous.x - vertex.x) * (point.y - vertex.y) / (previous.y - vertex.y)
+                    + vertex.x
+        {
+            is_inside = !is_inside;
+        }
+        previous = vertex;
+    }
+    is_inside
+}
+
+#[derive(Default)]
+struct TriggerSignal {
+    fired: Option<bool>,
+    waker: Option<Waker>,
+}
+
+/// Resolves to `true` once its trigger fires, or to `false` if the motion ends first.
+pub struct TriggerFuture {
+    signal: Rc<RefCell<TriggerSignal>>,
+}
+
+impl Future for TriggerFuture {
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut signal = self.signal.borrow_mut();
+        match signal.fired {
+            Some(fired) => Poll::Ready(fired),
+            None => {
+                signal.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+enum TriggerAction {
+    Callback(Box<dyn FnOnce()>),
+    Signal(Rc<RefCell<TriggerSignal>>),
+}
+
+impl TriggerAction {
+    fn resolve(self, fired: bool) {
+        match self {
+            Self::Callback(callback) => {
+                if fired {
+                    callback();
+                }
+            }
+            Self::Signal(signal) => {
+                let mut signal = signal.borrow_mut();
+                signal.fired = Some(fired);
+                if let Some(waker) = signal.waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+    }
+}
+
+pub(super) struct Trigger {
+    condition: TriggerCondition,
+    previous_heading_error: Option<f64>,
+    action: TriggerAction,
+}
+
+impl Trigger {
+    fn update(&mut self, pose: Pose, distance_traveled: f64, elapsed: Duration) -> bool {
+        match &mut self.condition {
+            TriggerCondition::Distance(distance) => distance_traveled >= *distance,
+            TriggerCondition::Elapsed(duration) => elapsed >= *duration,
+            TriggerCondition::Circle { center, radius } => {
+                (pose.position - *center).norm() <= *radius
+            }
+            TriggerCondition::Polygon(vertices) => is_inside_polygon(pose.position, vertices),
+            TriggerCondition::Heading(heading) => {
+                let error = angle_error(*heading, pose.orientation, true, None);
+                // A crossing flips the sign of the error, unless it is the wrap
+                // around on the opposite side.
+                let has_crossed = error == 0.0
+                    || self.previous_heading_error.is_some_and(|previous_error| {
+                        previous_error.signum() != error.signum()
+                            && previous_error.abs() < FRAC_PI_2
+                            && error.abs() < FRAC_PI_2
+                    });
+                self.previous_heading_error = Some(error);
+                has_crossed
+            }
+            TriggerCondition::Predicate(predicate) => predicate(pose),
+        }
+    }
+}
+
+impl MotionHandle {
+    fn add_trigger(&self, condition: TriggerCondition, action: TriggerAction) {
+        let mut state = self.state.borrow_mut();
+        if state.result.is_some() {
+            drop(state);
+            action.resolve(false);
+            return;
+        }
+        state.triggers.push(Trigger {
+            condition,
+            previous_heading_error: None,
+            action,
+        });
+    }
+
+    /// Calls `callback` once when the condition is met during the motion. It is
+    /// never called if the motion ends first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let handle = chassis.clone().move_to_point().target((24, 0)).call().await;
+    /// handle.on(TriggerCondition::circle(24, 0, 6.0), move || {
+    ///     clamp.set_state(true);
+    /// });
+    /// ```
+    pub fn on(&self, condition: TriggerCondition, callback: impl FnOnce() + 'static) {
+        self.add_trigger(condition, TriggerAction::Callback(Box::new(callback)));
+    }
+
+    /// Waits until the condition is met during the motion. It resolves to `false`
+    /// if the motion ends first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let handle = chassis.clone().move_relative().distance(24.0).call().await;
+    /// handle.when(TriggerCondition::Distance(12.0)).await;
+    /// intake.lock().await.spin();
+    /// ```
+    pub fn when(&self, condition: TriggerCondition) -> TriggerFuture {
+        let signal = Rc::new(RefCell::new(TriggerSignal::default()));
+        self.add_trigger(condition, TriggerAction::Signal(signal.clone()));
+        TriggerFuture { signal }
+    }
+
+    /// Fires the triggers whose conditions are met. The actions run after the
+    /// triggers are released, so that callbacks may register more triggers.
+    pub(super) fn update_triggers(&self, pose: Pose, distance_traveled: f64, elapsed: Duration) {
+        let triggers = mem::take(&mut self.state.borrow_mut().triggers);
+        let mut fired = Vec::new();
+        let mut remaining = Vec::with_capacity(triggers.len());
+        for mut trigger in triggers {
+            if trigger.update(pose, distance_traveled, elapsed) {
+                fired.push(trigger.action);
+            } else {
+                remaining.push(trigger);
+            }
+        }
+        {
+            let mut state = self.state.borrow_mut();
+            remaining.append(&mut state.triggers);
+            state.triggers = remaining;
+        }
+        for action in fired {
+            action.resolve(true);
+        }
+    }
+
+    /// Resolves the triggers that have not fired once the motion has ended.
+    pub(super) fn release_triggers(&self) {
+        let triggers = mem::take(&mut self.state.borrow_mut().triggers);
+        for trigger in triggers {
+            trigger.action.resolve(false);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use alloc::boxed::Box;
+
+use nalgebra::{Matrix2, Vector2};
+
+use super::Spline;
+
+/// A cubic Bézier curve from its first to its last control point, pulled towards
+/// the middle two.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CubicBezier {
+    pub control_points: [Vector2<f64>; 4],
+}
+
+impl CubicBezier {
+    pub fn new(
+        start: Vector2<f64>,
+        start_handle: Vector2<f64>,
+        end_handle: Vector2<f64>,
+        end: Vector2<f64>,
+    ) -> Self {
+        Self {
+            control_points: [start, start_handle, end_handle, end],
+        }
+    }
+
+    /// The Bézier curve with the given derivatives at its ends, as with a cubic Hermite
+    /// spline.
+    pub fn from_derivatives(
+        start: Vector2<f64>,
+        start_derivative: Vector2<f64>,
+        end: Vector2<f64>,
+        end_derivative: Vector2<f64>,
+    ) -> Self {
+        Self::new(
+            start,
+            start + start_derivative / 3.0,
+            end - end_derivative / 3.0,
+            end,
+        )
+    }
+}
+
+impl Spline for CubicBezier {
+    fn position(&self, t: f64) -> Vector2<f64> {
+        let [p0, p1, p2, p3] = self.control_points;
+        let u = 1.0 - t;
+        p0 * (u * u * u) + p1 * (3.0 * u * u * t) + p2 * (3.0 * u * t * t) + p3 * (t * t * t)
+    }
+
+    fn derivative(&self, t: f64) -> Vector2<f64> {
+        let [p0, p1, p2, p3] = self.control_points;
+        let u = 1.0 - t;
+        (p1 - p0) * (3.0 * u * u) + (p2 - p1) * (6.0 * u * t) + (p3 - p2) * (3.0 * t * t)
+    }
+
+    fn second_derivative(&self, t: f64) -> Vector2<f64> {
+        let [p0, p1, p2, p3] = self.control_points;
+        (p2 - p1 * 2.0 + p0) * (6.0 * (1.0 - t)) + (p3 - p2 * 2.0 + p1) * (6.0 * t)
+    }
+
+    fn transformed(&self, transform: &Matrix2<f64>) -> Box<dyn Spline> {
+        Box::new(Self {
+            control_points: self.control_points.map(|point| transform * point),
+        })
+    }
+}
@@ -0,0 +1,104 @@
+use alloc::boxed::Box;
+
+use nalgebra::{Matrix2, Vector2};
+
+use super::Spline;
+
+/// An end of a [`QuinticHermite`], with the first and second derivatives of the spline
+/// with respect to its parameter.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HermiteKnot {
+    pub position: Vector2<f64>,
+    pub velocity: Vector2<f64>,
+    pub acceleration: Vector2<f64>,
+}
+
+impl HermiteKnot {
+    pub fn new(position: Vector2<f64>, velocity: Vector2<f64>, acceleration: Vector2<f64>) -> Self {
+        Self {
+            position,
+            velocity,
+            acceleration,
+        }
+    }
+
+    fn transformed(&self, transform: &Matrix2<f64>) -> Self {
+        Self {
+            position: transform * self.position,
+            velocity: transform * self.velocity,
+            acceleration: transform * self.acceleration,
+        }
+    }
+}
+
+/// A quintic Hermite spline, which matches the position, velocity and acceleration
+/// at both ends so that splines sharing knots are continuous in curvature.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct QuinticHermite {
+    pub start: HermiteKnot,
+    pub end: HermiteKnot,
+}
+
+impl QuinticHermite {
+    pub fn new(start: HermiteKnot, end: HermiteKnot) -> Self {
+        Self { start, end }
+    }
+
+    /// Combines the knots with the weights of the six basis functions, in the order
+    /// of start position, velocity and acceleration, then end acceleration, velocity
+    /// and position.
+    fn combine(&self, weights: [f64; 6]) -> Vector2<f64> {
+        self.start.position * weights[0]
+            + self.start.velocity * weights[1]
+            + self.start.acceleration * weights[2]
+            + self.end.acceleration * weights[3]
+            + self.end.velocity * weights[4]
+            + self.end.position * weights[5]
+    }
+}
+
+impl Spline for QuinticHermite {
+    fn position(&self, t: f64) -> Vector2<f64> {
+        let (t2, t3) = (t * t, t * t * t);
+        let (t4, t5) = (t3 * t, t3 * t2);
+        self.combine([
+            1.0 - 10.0 * t3 + 15.0 * t4 - 6.0 * t5,
+            t - 6.0 * t3 + 8.0 * t4 - 3.0 * t5,
+            0.5 * t2 - 1.5 * t3 + 1.5 * t4 - 0.5 * t5,
+            0.5 * t3 - t4 + 0.5 * t5,
+            -4.0 * t3 + 7.0 * t4 - 3.0 * t5,
+            10.0 * t3 - 15.0 * t4 + 6.0 * t5,
+        ])
+    }
+
+    fn derivative(&self, t: f64) -> Vector2<f64> {
+        let (t2, t3, t4) = (t * t, t * t * t, t * t * t * t);
+        self.combine([
+            -30.0 * t2 + 60.0 * t3 - 30.0 * t4,
+            1.0 - 18.0 * t2 + 32.0 * t3 - 15.0 * t4,
+            t - 4.5 * t2 + 6.0 * t3 - 2.5 * t4,
+            1.5 * t2 - 4.0 * t3 + 2.5 * t4,
+            -12.0 * t2 + 28.0 * t3 - 15.0 * t4,
+            30.0 * t2 - 60.0 * t3 + 30.0 * t4,
+        ])
+    }
+
+    fn second_derivative(&self, t: f64) -> Vector2<f64> {
+        let (t2, t3) = (t * t, t * t * t);
+        self.combine([
+            -60.0 * t + 180.0 * t2 - 120.0 * t3,
+            -36.0 * t + 96.0 * t2 - 60.0 * t3,
+            1.0 - 9.0 * t + 18.0 * t2 - 10.0 * t3,
+            3.0 * t - 12.0 * t2 + 10.0 * t3,
+            -24.0 * t + 84.0 * t2 - 60.0 * t3,
+            60.0 * t - 180.0 * t2 + 120.0 * t3,
+        ])
+    }
+
+    fn transformed(&self, transform: &Matrix2<f64>) -> Box<dyn Spline> {
+        Box::new(Self {
+            start: self.start.transformed(transform),
+            end: self.end.transformed(transform),
+        })
+    }
+}
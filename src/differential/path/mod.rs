@@ -0,0 +1,391 @@
+pub mod bezier;
+pub mod hermite;
+pub mod waypoint;
+
+use alloc::{boxed::Box, vec, vec::Vec};
+
+use nalgebra::{Matrix2, Vector2};
+use num_traits::Float;
+
+use self::{
+    bezier::CubicBezier,
+    hermite::{HermiteKnot, QuinticHermite},
+    waypoint::WaypointPath,
+};
+use super::pose::Pose;
+
+dyn_clone::clone_trait_object!(Spline);
+
+/// A curve over a parameter from 0 to 1.
+pub trait Spline: dyn_clone::DynClone {
+    fn position(&self, t: f64) -> Vector2<f64>;
+    fn derivative(&self, t: f64) -> Vector2<f64>;
+    fn second_derivative(&self, t: f64) -> Vector2<f64>;
+
+    /// The spline with a linear map, such as a reflection, applied to it.
+    fn transformed(&self, transform: &Matrix2<f64>) -> Box<dyn Spline>;
+}
+
+/// How smoothly the segments of a [`Path`] join, as the highest derivative with
+/// respect to the parameter that matches on both sides of every joint.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Continuity {
+    C0,
+    C1,
+    C2,
+}
+
+/// A parameter value at a distance along the path.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct ArcLengthSample {
+    distance: f64,
+    segment: usize,
+    t: f64,
+}
+
+/// Subintervals of each segment in the arc length table.
+const ARC_LENGTH_SAMPLES: usize = 32;
+
+/// Gauss–Legendre nodes on [-1, 1] and their weights.
+const GAUSS_LEGENDRE: [(f64, f64); 5] = [
+    (0.0, 0.568_888_888_888_888_9),
+    (-0.538_469_310_105_683_1, 0.478_628_670_499_366_5),
+    (0.538_469_310_105_683_1, 0.478_628_670_499_366_5),
+    (-0.906_179_845_938_664, 0.236_926_885_056_189_1),
+    (0.906_179_845_938_664, 0.236_926_885_056_189_1),
+];
+
+/// Position tolerance in inches for segments to count as joined, and the tolerance
+/// for derivatives to match in [`Path::continuity`].
+const JOINT_TOLERANCE: f64 = 1e-6;
+
+/// The length of a spline between two parameter values.
+fn arc_length(spline: &dyn Spline, start: f64, end: f64) -> f64 {
+    let half_width = (end - start) / 2.0;
+    let midpoint = (start + end) / 2.0;
+    GAUSS_LEGENDRE
+        .iter()
+        .map(|&(node, weight)| weight * spline.derivative(midpoint + half_width * node).norm())
+        .sum::<f64>()
+        * half_width
+}
+
+/// A chain of splines, queried by the distance along it.
+///
+/// Headings follow the convention of [`Pose`], counterclockwise from the positive x axis,
+/// and curvature is positive when the path turns counterclockwise.
+///
+/// # Example
+///
+/// ```
+/// let path = Path::through_poses(&[
+///     Pose::new(-48.0, -24.0, 0.0),
+///     Pose::new(-24.0, -12.0, 45.0.hdg_deg()),
+///     Pose::new(0.0, 0.0, 0.0),
+/// ]);
+/// let blue_path = path.mirrored_x();
+/// let midpoint = path.pose_at(path.length() / 2.0);
+/// ```
+#[derive(Clone)]
+pub struct Path {
+    segments: Vec<Box<dyn Spline>>,
+    arc_length_table: Vec<ArcLengthSample>,
+}
+
+impl Path {
+    /// # Panics
+    ///
+    /// Panics if there are no segments or if a segment does not start where the
+    /// previous one ends.
+    pub fn new(segments: Vec<Box<dyn Spline>>) -> Self {
+        assert!(
+            !segments.is_empty(),
+            "A path requires at least one segment."
+        );
+        for window in segments.windows(2) {
+            assert!(
+                (window[0].position(1.0) - window[1].position(0.0)).norm() < JOINT_TOLERANCE,
+                "Each segment must start where the previous one ends."
+            );
+        }
+
+        let mut arc_length_table = Vec::with_capacity(segments.len() * (ARC_LENGTH_SAMPLES + 1));
+        let mut distance = 0.0;
+        for (segment, spline) in segments.iter().enumerate() {
+            let lengths = (0..ARC_LENGTH_SAMPLES)
+                .map(|i| {
+                    let start = i as f64 / ARC_LENGTH_SAMPLES as f64;
+                    let end = (i + 1) as f64 / ARC_LENGTH_SAMPLES as f64;
+                    arc_length(spline.as_ref(), start, end)
+                })
+                .collect::<Vec<_>>();
+
+            // Leave out segments of zero length, such as between repeated poses, since
+            // they have no heading.
+            if lengths.iter().sum::<f64>() <= 0.0 && !arc_length_table.is_empty() {
+                continue;
+            }
+            arc_length_table.push(ArcLengthSample {
+                distance,
+                segment,
+                t: 0.0,
+            });
+            for (i, length) in lengths.into_iter().enumerate() {
+                distance += length;
+                arc_length_table.push(ArcLengthSample {
+                    distance,
+                    segment,
+                    t: (i + 1) as f64 / ARC_LENGTH_SAMPLES as f64,
+                });
+            }
+        }
+        Self {
+            segments,
+            arc_length_table,
+        }
+    }
+
+    /// Cubic Bézier segments through the poses, leaving each along its heading.
+    /// Neighboring segments share the derivative at each pose, so the path is C1.
+    pub fn through_poses(poses: &[Pose]) -> Self {
+        let derivatives = Self::pose_derivatives(poses);
+        Self::new(
+            poses
+                .windows(2)
+                .zip(derivatives.windows(2))
+                .map(|(poses, derivatives)| {
+                    Box::new(CubicBezier::from_derivatives(
+                        poses[0].position,
+                        derivatives[0],
+                        poses[1].position,
+                        derivatives[1],
+                    )) as Box<dyn Spline>
+                })
+                .collect(),
+        )
+    }
+
+    /// Quintic Hermite segments through the poses, leaving each along its heading.
+    /// Neighboring segments share the first and second derivatives at each pose, so
+    /// the path is C2 and its curvature has no jumps.
+    pub fn quintic_through_poses(poses: &[Pose]) -> Self {
+        let derivatives = Self::pose_derivatives(poses);
+
+        // Average the accelerations at the ends of the cubic segments meeting at each pose.
+        let mut accelerations = vec![Vector2::zeros(); poses.len()];
+        let mut counts = vec![0.0; poses.len()];
+        for i in 0..poses.len().saturating_sub(1) {
+            let chord = poses[i + 1].position - poses[i].position;
+            accelerations[i] += chord * 6.0 - derivatives[i] * 4.0 - derivatives[i + 1] * 2.0;
+            accelerations[i + 1] += -chord * 6.0 + derivatives[i] * 2.0 + derivatives[i + 1] * 4.0;
+            counts[i] += 1.0;
+            counts[i + 1] += 1.0;
+        }
+
+        let knots = (0..poses.len())
+            .map(|i| {
+                HermiteKnot::new(
+                    poses[i].position,
+                    derivatives[i],
+                    accelerations[i] / f64::max(counts[i], 1.0),
+                )
+            })
+            .collect::<Vec<_>>();
+        Self::new(
+            knots
+                .windows(2)
+                .map(|knots| Box::new(QuinticHermite::new(knots[0], knots[1])) as Box<dyn Spline>)
+                .collect(),
+        )
+    }
+
+    /// A natural cubic spline through the points as Bézier segments, which is C2 and
+    /// has no curvature at its ends.
+    pub fn natural_spline(points: &[Vector2<f64>]) -> Self {
+        assert!(points.len() >= 2, "A spline requires at least two points.");
+        let n = points.len();
+
+        // Solve the tridiagonal system for the derivative at each point with the Thomas
+        // algorithm, where the diagonal is 2 at the ends and 4 elsewhere.
+        let diagonal = |i: usize| if i == 0 || i == n - 1 { 2.0 } else { 4.0 };
+        let right_hand_side = |i: usize| {
+            if i == 0 {
+                (points[1] - points[0]) * 3.0
+            } else if i == n - 1 {
+                (points[n - 1] - points[n - 2]) * 3.0
+            } else {
+                (points[i + 1] - points[i - 1]) * 3.0
+            }
+        };
+        let mut upper = vec![0.0; n];
+        let mut derivatives = vec![Vector2::zeros(); n];
+        upper[0] = 1.0 / diagonal(0);
+        derivatives[0] = right_hand_side(0) / diagonal(0);
+        for i in 1..n {
+            let denominator = diagonal(i) - upper[i - 1];
+            upper[i] = 1.0 / denominator;
+            derivatives[i] = (right_hand_side(i) - derivatives[i - 1]) / denominator;
+        }
+        for i in (0..n - 1).rev() {
+            derivatives[i] = derivatives[i] - derivatives[i + 1] * upper[i];
+        }
+
+        Self::new(
+            (0..n - 1)
+                .map(|i| {
+                    Box::new(CubicBezier::from_derivatives(
+                        points[i],
+                        derivatives[i],
+                        points[i + 1],
+                        derivatives[i + 1],
+                    )) as Box<dyn Spline>
+                })
+                .collect(),
+        )
+    }
+
+    /// The derivative at each pose along its heading, with a magnitude of the average
+    /// length of the chords to its neighbors.
+    fn pose_derivatives(poses: &[Pose]) -> Vec<Vector2<f64>> {
+        assert!(poses.len() >= 2, "A path requires at least two poses.");
+        let chords = poses
+            .windows(2)
+            .map(|window| window[0].distance_to(&window[1]))
+            .collect::<Vec<_>>();
+        poses
+            .iter()
+            .enumerate()
+            .map(|(i, pose)| {
+                let magnitude = match i {
+                    0 => chords[0],
+                    i if i == chords.len() => chords[i - 1],
+                    i => (chords[i - 1] + chords[i]) / 2.0,
+                };
+                Vector2::new(pose.orientation.cos(), pose.orientation.sin()) * magnitude
+            })
+            .collect()
+    }
+
+    pub fn segments(&self) -> &[Box<dyn Spline>] {
+        &self.segments
+    }
+
+    pub fn length(&self) -> f64 {
+        self.arc_length_table[self.arc_length_table.len() - 1].distance
+    }
+
+    /// The lowest continuity of any joint, or [`Continuity::C2`] for a single segment.
+    pub fn continuity(&self) -> Continuity {
+        self.segments
+            .windows(2)
+            .map(|window| {
+                let (previous, next) = (&window[0], &window[1]);
+                if (previous.derivative(1.0) - next.derivative(0.0)).norm() > JOINT_TOLERANCE {
+                    Continuity::C0
+                } else if (previous.second_derivative(1.0) - next.second_derivative(0.0)).norm()
+                    > JOINT_TOLERANCE
+                {
+                    Continuity::C1
+                } else {
+                    Continuity::C2
+                }
+            })
+            .min()
+            .unwrap_or(Continuity::C2)
+    }
+
+    /// The segment and its parameter at a distance along the path, clamped to its ends.
+    fn parameter_at(&self, distance: f64) -> (usize, f64) {
+        let table = &self.arc_length_table;
+        let distance = distance.clamp(0.0, self.length());
+        let i = table.partition_point(|sample| sample.distance <= distance);
+        if i >= table.len() {
+            let last = table[table.len() - 1];
+            return (last.segment, last.t);
+        }
+        // The first sample has a distance of zero, so `i` is at least 1.
+        let (previous, next) = (table[i - 1], table[i]);
+        let spline = self.segments[next.segment].as_ref();
+
+        // Interpolate within the sample, then refine with Newton's method.
+        let sample_length = next.distance - previous.distance;
+        if sample_length <= 0.0 {
+            return (next.segment, next.t);
+        }
+        let mut t =
+            previous.t + (next.t - previous.t) * (distance - previous.distance) / sample_length;
+        for _ in 0..2 {
+            let speed = spline.derivative(t).norm();
+            if speed <= 0.0 {
+                break;
+            }
+            let error = previous.distance + arc_length(spline, previous.t, t) - distance;
+            t = (t - error / speed).clamp(previous.t, next.t);
+        }
+        (next.segment, t)
+    }
+
+    pub fn point_at(&self, distance: f64) -> Vector2<f64> {
+        let (segment, t) = self.parameter_at(distance);
+        self.segments[segment].position(t)
+    }
+
+    /// The heading of the tangent at a distance along the path, in radians.
+    pub fn heading_at(&self, distance: f64) -> f64 {
+        let (segment, t) = self.parameter_at(distance);
+        let derivative = self.segments[segment].derivative(t);
+        derivative.y.atan2(derivative.x)
+    }
+
+    /// The signed curvature at a distance along the path, in inverse inches.
+    pub fn curvature_at(&self, distance: f64) -> f64 {
+        let (segment, t) = self.parameter_at(distance);
+        let derivative = self.segments[segment].derivative(t);
+        let speed = derivative.norm();
+        if speed <= 0.0 {
+            return 0.0;
+        }
+        derivative.perp(&self.segments[segment].second_derivative(t)) / speed.powi(3)
+    }
+
+    /// The point and tangent heading at a distance along the path.
+    pub fn pose_at(&self, distance: f64) -> Pose {
+        let point = self.point_at(distance);
+        Pose::new(point.x, point.y, self.heading_at(distance))
+    }
+
+    /// The path with a linear map, such as a reflection, applied to it.
+    pub fn transformed(&self, transform: &Matrix2<f64>) -> Self {
+        Self::new(
+            self.segments
+                .iter()
+                .map(|segment| segment.transformed(transform))
+                .collect(),
+        )
+    }
+
+    /// The path reflected across the y axis, such as to run a routine for the other alliance.
+    pub fn mirrored_x(&self) -> Self {
+        self.transformed(&Matrix2::new(-1.0, 0.0, 0.0, 1.0))
+    }
+
+    /// The path reflected across the x axis.
+    pub fn mirrored_y(&self) -> Self {
+        self.transformed(&Matrix2::new(1.0, 0.0, 0.0, -1.0))
+    }
+
+    /// Samples the path at evenly spaced distances for [`super::chassis::Chassis::follow_path`].
+    pub fn to_waypoints(&self, spacing: f64) -> WaypointPath {
+        assert!(spacing > 0.0, "The spacing must be positive.");
+        let length = self.length();
+        let count = (length / spacing).ceil().max(1.0) as usize;
+        WaypointPath::from(
+            (0..=count)
+                .map(|i| {
+                    let point = self.point_at(length * i as f64 / count as f64);
+                    (point.x, point.y)
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+}
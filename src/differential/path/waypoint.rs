@@ -0,0 +1,225 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::Vector2;
+use num_traits::{AsPrimitive, Float};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Waypoint {
+    pub position: Vector2<f64>,
+
+    /// The speed to pass this waypoint at, out of 1. If `None`, it is the maximum speed.
+    pub speed: Option<f64>,
+}
+
+impl<T: AsPrimitive<f64>, U: AsPrimitive<f64>> From<(T, U)> for Waypoint {
+    fn from((x, y): (T, U)) -> Self {
+        Self {
+            position: Vector2::new(x.as_(), y.as_()),
+            speed: None,
+        }
+    }
+}
+
+impl<T: AsPrimitive<f64>, U: AsPrimitive<f64>, V: AsPrimitive<f64>> From<(T, U, V)> for Waypoint {
+    fn from((x, y, speed): (T, U, V)) -> Self {
+        Self {
+            position: Vector2::new(x.as_(), y.as_()),
+            speed: Some(speed.as_()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PathParseError {
+    /// The path has no waypoints.
+    Empty,
+
+    /// The line, counting from 1, does not have two or three numbers.
+    InvalidLine(usize),
+}
+
+impl core::fmt::Display for PathParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "The path has no waypoints."),
+            Self::InvalidLine(line) => write!(f, "Line {} is not a valid waypoint.", line),
+        }
+    }
+}
+
+/// Waypoints joined by straight segments, for
+/// [`Chassis::follow_path`](crate::differential::chassis::Chassis::follow_path).
+#[derive(Clone, PartialEq, Debug)]
+pub struct WaypointPath {
+    waypoints: Vec<Waypoint>,
+
+    /// The distance along the path to each waypoint.
+    distances: Vec<f64>,
+
+    /// The curvature of the path at each waypoint, from the circle through it and its
+    /// neighbors. It is zero at the ends.
+    curvatures: Vec<f64>,
+}
+
+impl WaypointPath {
+    pub fn new(waypoints: Vec<Waypoint>) -> Self {
+        assert!(
+            !waypoints.is_empty(),
+            "A path requires at least one waypoint."
+        );
+        let mut distances = Vec::with_capacity(waypoints.len());
+        distances.push(0.0);
+        for window in waypoints.windows(2) {
+            distances.push(
+                distances[distances.len() - 1]
+                    + window[0].position.metric_distance(&window[1].position),
+            );
+        }
+
+        let mut curvatures = vec![0.0; waypoints.len()];
+        for (i, window) in waypoints.windows(3).enumerate() {
+            let a = window[1].position - window[0].position;
+            let b = window[2].position - window[1].position;
+            let c = window[2].position - window[0].position;
+            let denominator = a.norm() * b.norm() * c.norm();
+            if denominator > 0.0 {
+                curvatures[i + 1] = (2.0 * a.perp(&b) / denominator).abs();
+            }
+        }
+        Self {
+            waypoints,
+            distances,
+            curvatures,
+        }
+    }
+
+    /// Parses one waypoint per line as `x, y` or `x, y, speed`, such as the paths
+    /// exported by path.jerryio. Blank lines and lines starting with `#` are skipped,
+    /// and parsing stops at a line of `endData`.
+    pub fn parse(text: &str) -> Result<Self, PathParseError> {
+        let mut waypoints = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line == "endData" {
+                break;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let values = line
+                .split(',')
+                .map(|value| value.trim().parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| PathParseError::InvalidLine(i + 1))?;
+            waypoints.push(match values[..] {
+                [x, y] => Waypoint::from((x, y)),
+                [x, y, speed] => Waypoint::from((x, y, speed)),
+                _ => return Err(PathParseError::InvalidLine(i + 1)),
+            });
+        }
+        if waypoints.is_empty() {
+            return Err(PathParseError::Empty);
+        }
+        Ok(Self::new(waypoints))
+    }
+
+    pub fn waypoints(&self) -> &[Waypoint] {
+        &self.waypoints
+    }
+
+    pub fn length(&self) -> f64 {
+        self.distances[self.distances.len() - 1]
+    }
+
+    /// The distance along the path to the start of its last segment.
+    pub(crate) fn last_segment_start(&self) -> f64 {
+        self.distances[self.segment_at(self.length())]
+    }
+
+    /// The index of the segment containing the distance along the path,
+    /// where segment `i` starts at waypoint `i`.
+    fn segment_at(&self, distance: f64) -> usize {
+        self.distances
+            .partition_point(|&segment_start| segment_start <= distance)
+            .clamp(1, self.waypoints.len().max(2) - 1)
+            - 1
+    }
+
+    /// How far along segment `segment` a distance along the path is, from 0 to 1.
+    fn segment_fraction(&self, segment: usize, distance: f64) -> f64 {
+        if segment + 1 >= self.waypoints.len() {
+            return 0.0;
+        }
+        let length = self.distances[segment + 1] - self.distances[segment];
+        if length > 0.0 {
+            ((distance - self.distances[segment]) / length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// The point at a distance along the path, clamped to its ends.
+    pub fn point_at(&self, distance: f64) -> Vector2<f64> {
+        let segment = self.segment_at(distance);
+        if segment + 1 >= self.waypoints.len() {
+            return self.waypoints[segment].position;
+        }
+        self.waypoints[segment].position.lerp(
+            &self.waypoints[segment + 1].position,
+            self.segment_fraction(segment, distance),
+        )
+    }
+
+    /// The speed at a distance along the path, interpolated between waypoints.
+    pub(crate) fn speed_at(&self, distance: f64, max_speed: f64) -> f64 {
+        let segment = self.segment_at(distance);
+        let speed = |i: usize| self.waypoints[i].speed.unwrap_or(max_speed);
+        if segment + 1 >= self.waypoints.len() {
+            return speed(segment);
+        }
+        let t = self.segment_fraction(segment, distance);
+        speed(segment) * (1.0 - t) + speed(segment + 1) * t
+    }
+
+    /// The curvature at a distance along the path, interpolated between waypoints.
+    pub(crate) fn curvature_at(&self, distance: f64) -> f64 {
+        let segment = self.segment_at(distance);
+        if segment + 1 >= self.waypoints.len() {
+            return self.curvatures[segment];
+        }
+        let t = self.segment_fraction(segment, distance);
+        self.curvatures[segment] * (1.0 - t) + self.curvatures[segment + 1] * t
+    }
+
+    /// The distance along the path to the point closest to `position`, searching only
+    /// between `start` and `end` so that the robot cannot skip ahead where the path
+    /// crosses itself.
+    pub(crate) fn closest_distance(&self, position: Vector2<f64>, start: f64, end: f64) -> f64 {
+        if self.waypoints.len() < 2 {
+            return 0.0;
+        }
+        let mut closest = (f64::MAX, start);
+        for segment in self.segment_at(start)..=self.segment_at(end) {
+            let segment_start = self.waypoints[segment].position;
+            let direction = self.waypoints[segment + 1].position - segment_start;
+            let length_squared = direction.norm_squared();
+            let t = if length_squared > 0.0 {
+                ((position - segment_start).dot(&direction) / length_squared).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let distance = (self.distances[segment] + t * length_squared.sqrt()).clamp(start, end);
+            let error = (self.point_at(distance) - position).norm_squared();
+            if error < closest.0 {
+                closest = (error, distance);
+            }
+        }
+        closest.1
+    }
+}
+
+impl<W: Into<Waypoint>> From<Vec<W>> for WaypointPath {
+    fn from(waypoints: Vec<W>) -> Self {
+        Self::new(waypoints.into_iter().map(Into::into).collect())
+    }
+}
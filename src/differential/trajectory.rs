@@ -0,0 +1,243 @@
+use alloc::vec::Vec;
+use core::{f64::consts::PI, time::Duration};
+
+use bon::Builder;
+use num_traits::Float;
+
+use super::{path::Path, pose::Pose};
+use crate::utils::math::angle_error;
+
+/// Limits on the motion of the robot along a trajectory, in inches and seconds.
+#[derive(Clone, Copy, PartialEq, Builder)]
+pub struct TrajectoryConstraints {
+    pub max_velocity: f64,
+    pub max_acceleration: f64,
+
+    /// Limits the speed in turns to `sqrt(max_centripetal_acceleration / |curvature|)`.
+    #[builder(default = f64::INFINITY)]
+    pub max_centripetal_acceleration: f64,
+
+    /// Limits the speed so that the outer wheels stay under this speed in turns.
+    #[builder(default = f64::INFINITY)]
+    pub max_wheel_velocity: f64,
+
+    /// Distance between the left and right wheels, for the wheel velocity limit.
+    #[builder(default = 0.0)]
+    pub track_width: f64,
+
+    #[builder(default = 0.0)]
+    pub start_velocity: f64,
+
+    #[builder(default = 0.0)]
+    pub end_velocity: f64,
+
+    /// Drives the path backwards, facing away from its tangent.
+    #[builder(default = false)]
+    pub reversed: bool,
+}
+
+#[macro_export]
+macro_rules! params_trajectory {
+    (
+        $($key:ident : $value:expr),* $(,)?
+    ) => {
+        $crate::differential::trajectory::TrajectoryConstraints::builder()
+            $(.$key($value))*
+            .build()
+    };
+}
+pub use params_trajectory;
+
+/// The reference for the robot at a time along a [`Trajectory`].
+///
+/// Velocities and accelerations are negative when reversing, and the curvature is
+/// that of the robot's motion, so that the angular velocity is always the linear
+/// velocity times the curvature.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TrajectoryState {
+    pub time: Duration,
+    pub pose: Pose,
+
+    /// The distance along the path in inches.
+    pub distance: f64,
+
+    /// Linear velocity in inches per second.
+    pub linear_velocity: f64,
+
+    /// Angular velocity in radians per second, counterclockwise positive.
+    pub angular_velocity: f64,
+
+    /// Linear acceleration in inches per second squared.
+    pub acceleration: f64,
+
+    /// Curvature in inverse inches, counterclockwise positive.
+    pub curvature: f64,
+}
+
+/// A path with a velocity profile, sampled by time for a tracking controller such as
+/// [`crate::controllers::ramsete::RAMSETE`].
+///
+/// # Example
+///
+/// ```
+/// let trajectory = Trajectory::generate(
+///     &Path::quintic_through_poses(&[Pose::new(-48, -24, 0), Pose::new(0, 0, 90.0.hdg_deg())]),
+///     params_trajectory!(
+///         max_velocity: 60.0,
+///         max_acceleration: 120.0,
+///         max_centripetal_acceleration: 80.0,
+///     ),
+/// );
+/// let halfway = trajectory.sample(trajectory.duration() / 2);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct Trajectory {
+    states: Vec<TrajectoryState>,
+}
+
+impl Trajectory {
+    /// Spacing in inches between the states of a generated trajectory.
+    pub const RESOLUTION: f64 = 0.5;
+
+    /// Generates the fastest velocity profile along the path within the constraints,
+    /// with forward and backward passes over the velocity limit at each state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the maximum velocity or acceleration is not positive.
+    pub fn generate(path: &Path, constraints: TrajectoryConstraints) -> Self {
+        assert!(
+            constraints.max_velocity > 0.0 && constraints.max_acceleration > 0.0,
+            "The maximum velocity and acceleration must be positive."
+        );
+        let length = path.length();
+        let count = ((length / Self::RESOLUTION).ceil() as usize).max(1);
+        let step = length / count as f64;
+        let distances = (0..=count).map(|i| i as f64 * step).collect::<Vec<_>>();
+        let curvatures = distances
+            .iter()
+            .map(|&distance| path.curvature_at(distance))
+            .collect::<Vec<_>>();
+
+        let mut velocities = curvatures
+            .iter()
+            .map(|curvature| {
+                let curvature = curvature.abs();
+                constraints
+                    .max_velocity
+                    .min((constraints.max_centripetal_acceleration / curvature).sqrt())
+                    .min(
+                        constraints.max_wheel_velocity
+                            / (1.0 + curvature * constraints.track_width / 2.0),
+                    )
+            })
+            .collect::<Vec<_>>();
+        velocities[0] = velocities[0].min(constraints.start_velocity.abs());
+        velocities[count] = velocities[count].min(constraints.end_velocity.abs());
+        for i in 1..=count {
+            velocities[i] = velocities[i].min(
+                (velocities[i - 1].powi(2) + 2.0 * constraints.max_acceleration * step).sqrt(),
+            );
+        }
+        for i in (0..count).rev() {
+            velocities[i] = velocities[i].min(
+                (velocities[i + 1].powi(2) + 2.0 * constraints.max_acceleration * step).sqrt(),
+            );
+        }
+
+        let direction = if constraints.reversed { -1.0 } else { 1.0 };
+        let mut states = Vec::with_capacity(count + 1);
+        let mut time = 0.0;
+        for i in 0..=count {
+            let acceleration = if i < count && step > 0.0 {
+                (velocities[i + 1].powi(2) - velocities[i].powi(2)) / (2.0 * step)
+            } else {
+                0.0
+            };
+            let mut pose = path.pose_at(distances[i]);
+            if constraints.reversed {
+                pose.orientation += PI;
+            }
+            let linear_velocity = direction * velocities[i];
+            let curvature = direction * curvatures[i];
+            states.push(TrajectoryState {
+                time: Duration::from_secs_f64(time),
+                pose,
+                distance: distances[i],
+                linear_velocity,
+                angular_velocity: linear_velocity * curvature,
+                acceleration: direction * acceleration,
+                curvature,
+            });
+            if i < count {
+                let average_velocity = (velocities[i] + velocities[i + 1]) / 2.0;
+                time += if average_velocity > 0.0 {
+                    step / average_velocity
+                } else {
+                    0.0
+                };
+            }
+        }
+        Self { states }
+    }
+
+    /// Generates a trajectory through the poses along a C2 quintic spline.
+    pub fn from_poses(poses: &[Pose], constraints: TrajectoryConstraints) -> Self {
+        Self::generate(&Path::quintic_through_poses(poses), constraints)
+    }
+
+    pub fn states(&self) -> &[TrajectoryState] {
+        &self.states
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.states[self.states.len() - 1].time
+    }
+
+    pub fn initial_state(&self) -> TrajectoryState {
+        self.states[0]
+    }
+
+    pub fn final_state(&self) -> TrajectoryState {
+        self.states[self.states.len() - 1]
+    }
+
+    /// The state at a time, at constant acceleration between the generated states and
+    /// clamped to the ends of the trajectory.
+    pub fn sample(&self, time: Duration) -> TrajectoryState {
+        let i = self.states.partition_point(|state| state.time <= time);
+        if i == 0 {
+            return self.initial_state();
+        }
+        if i >= self.states.len() {
+            return self.final_state();
+        }
+        let (previous, next) = (&self.states[i - 1], &self.states[i]);
+        let elapsed = (time - previous.time).as_secs_f64();
+        let linear_velocity = previous.linear_velocity + previous.acceleration * elapsed;
+        let traveled = (previous.linear_velocity * elapsed
+            + previous.acceleration * elapsed.powi(2) / 2.0)
+            .abs();
+        let fraction = if next.distance > previous.distance {
+            (traveled / (next.distance - previous.distance)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let curvature = previous.curvature + (next.curvature - previous.curvature) * fraction;
+        TrajectoryState {
+            time,
+            pose: Pose {
+                position: previous.pose.position.lerp(&next.pose.position, fraction),
+                orientation: previous.pose.orientation
+                    + angle_error(next.pose.orientation, previous.pose.orientation, true, None)
+                        * fraction,
+            },
+            distance: previous.distance + (next.distance - previous.distance) * fraction,
+            linear_velocity,
+            angular_velocity: linear_velocity * curvature,
+            acceleration: previous.acceleration,
+            curvature,
+        }
+    }
+}
@@ -16,14 +16,16 @@ use core::{
 use auton_routines::AutonRoutine;
 use autons::{prelude::*, simple::SimpleSelect};
 use lamlib_rs::{
-    controllers::pid::PID,
+    controllers::{pid::PID, ramsete::RAMSETE},
     devices::{motor_group::MotorGroup, pneumatics::PneumaticWrapper},
     differential::{
         chassis::{Chassis, Drivetrain, MotionSettings},
         drive_curve::exponential::ExponentialDriveCurve,
+        kinematics::DifferentialDriveKinematics,
         motions::{
             angular::TurnToSettings, boomerang::BoomerangSettings, linear::MoveToPointSettings,
-            ramsete::RAMSETEHybridSettings, Tolerance, ToleranceGroup,
+            pure_pursuit::PurePursuitSettings, ramsete::RAMSETEHybridSettings,
+            trajectory::FollowTrajectorySettings, Tolerance, ToleranceGroup,
         },
         pose::Pose,
     },
@@ -251,6 +253,16 @@ async fn main(peripherals: Peripherals) {
             angular_tolerances.clone(),
             1.0,
         )),
+        RefCell::new(PurePursuitSettings::new(
+            linear_controller.clone(),
+            linear_tolerances.clone(),
+            12.0, // Track width in inches.
+        )),
+        RefCell::new(FollowTrajectorySettings::new(
+            Box::new(RAMSETE::new(0.0013, 0.7)),
+            DifferentialDriveKinematics::new(12.0),
+            76.6, // 3.25" wheels at 450 RPM, in inches per second.
+        )),
     );
 
     let _chassis_velocity_controller = PID::new(1.0, 1.0, 0.0, 0.0, true, 2);
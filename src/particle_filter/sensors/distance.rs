@@ -1,4 +1,4 @@
-use alloc::rc::Rc;
+use alloc::{boxed::Box, rc::Rc};
 use core::{cell::RefCell, f32::consts::PI, time::Duration};
 
 use nalgebra::{Matrix1xX, Matrix2, Matrix2xX, Matrix3xX, RowDVector, Vector2, Vector3};
@@ -7,6 +7,7 @@ use vexide::{float::Float, prelude::DistanceSensor, time::Instant};
 
 use super::ParticleFilterSensor;
 use crate::utils::{
+    filters::Filter,
     math::lerp,
     samplers::{multivariate_gaussian_sampler::GaussianSampler, MultivariateSampler},
     FIELD_WALL,
@@ -64,6 +65,9 @@ pub struct LiDAR<T: Rng> {
     /// Distance sensors update at approximately 30hz≈30ms/each.
     /// This is to store the last time it was polled at.
     last_updated: Option<Instant>,
+
+    /// Filters the scaled distance in millimeters, if set.
+    distance_filter: Option<Box<dyn Filter<f32>>>,
 }
 impl<T: Rng> LiDAR<T> {
     pub fn new(
@@ -89,8 +93,17 @@ impl<T: Rng> LiDAR<T> {
             precompute_data,
             scalar: scalar.unwrap_or(1.0),
             last_updated: None,
+            distance_filter: None,
         }
     }
+
+    /// Filters readings before they are compared against the particles,
+    /// such as with a [`crate::utils::filters::median::MedianFilter`] to reject
+    /// spurious readings.
+    pub fn with_distance_filter(mut self, distance_filter: Box<dyn Filter<f32>>) -> Self {
+        self.distance_filter = Some(distance_filter);
+        self
+    }
 }
 
 impl<T: Rng> ParticleFilterSensor<3> for LiDAR<T> {
@@ -124,7 +137,10 @@ impl<T: Rng> ParticleFilterSensor<3> for LiDAR<T> {
         let detected_object = self.distance_sensor.object().unwrap_or(None);
 
         if let Some(detected_distance_mm) = detected_object.as_ref().map(|object| object.distance) {
-            let detected_distance_mm = detected_distance_mm as f32 * self.scalar;
+            let mut detected_distance_mm = detected_distance_mm as f32 * self.scalar;
+            if let Some(distance_filter) = &mut self.distance_filter {
+                detected_distance_mm = distance_filter.update(detected_distance_mm);
+            }
             self.global_angles = positions
                 .row(2)
                 .map(|orientation| orientation + self.sensor_offset[2]);
@@ -7,7 +7,11 @@ use vexide::{
     time::Instant,
 };
 
-use lamlib_rs::{avg_valid, devices::motor_group::MotorGroup, utils::AllianceColor};
+use lamlib_rs::{
+    avg_valid,
+    devices::motor_group::MotorGroup,
+    utils::{filters::Filter, AllianceColor},
+};
 
 #[derive(PartialEq)]
 enum IntakeButtonState {
@@ -37,6 +41,10 @@ pub struct Intake {
     optical_color_history: VecDeque<(Option<AllianceColor>, Instant)>,
     optical_sort_delay: Option<Duration>,
 
+    /// Filters the optical hue, if set. The filters treat hue as a linear value, so
+    /// they are wrong where it wraps around at red, between 360 and 0.
+    hue_filter: Option<Box<dyn Filter<f64>>>,
+
     /// Optical callback ran on every optical sampling. Use for timed intake stops, etc.
     optical_callback: RefCell<Box<dyn Fn(AllianceColor) -> bool>>,
 
@@ -59,6 +67,9 @@ pub struct Intake {
 
     jam_start_time: Option<Instant>,
     jam_detected: bool,
+
+    /// Filters the average motor velocity for the anti-jam check, if set.
+    velocity_filter: Option<Box<dyn Filter<f64>>>,
     additional_anti_jam_criterion: Option<Box<dyn Fn() -> Pin<Box<dyn Future<Output = bool>>>>>,
 
     task: Option<Task<()>>,
@@ -99,6 +110,8 @@ impl Intake {
             optical_sort_delay,
             distance_sort_delay,
             optical_callback: default_optical_callback!(),
+            hue_filter: None,
+            velocity_filter: None,
         }))
     }
     pub fn set_velocity(&mut self, velocity: f64) {
@@ -116,6 +129,12 @@ impl Intake {
     pub fn set_sort_state(&mut self, state: bool) {
         self.is_sort_enabled = state;
     }
+    pub fn set_hue_filter(&mut self, hue_filter: Option<Box<dyn Filter<f64>>>) {
+        self.hue_filter = hue_filter;
+    }
+    pub fn set_velocity_filter(&mut self, velocity_filter: Option<Box<dyn Filter<f64>>>) {
+        self.velocity_filter = velocity_filter;
+    }
 
     /// Adds braking for 0.0 velocity.
     fn spin_at_velocity(&self, motor_group: &mut MotorGroup, velocity: f64) {
@@ -167,6 +186,10 @@ impl Intake {
             let hue = optical.hue();
             let proximity = optical.proximity();
             if let Ok(hue) = hue {
+                let hue = match &mut self.hue_filter {
+                    Some(hue_filter) => hue_filter.update(hue),
+                    None => hue,
+                };
                 if let Ok(proximity) = proximity {
                     if proximity < 90.0 {
                         return {
@@ -298,7 +321,13 @@ impl Intake {
                 .into_iter()
                 .map(|v| v.ok())
                 .collect::<Vec<_>>();
-            let actual_velocity: Option<f64> = avg_valid!(actual_velocities);
+            let actual_velocity: Option<f64> =
+                avg_valid!(actual_velocities).map(|actual_velocity| {
+                    match &mut self.velocity_filter {
+                        Some(velocity_filter) => velocity_filter.update(actual_velocity),
+                        None => actual_velocity,
+                    }
+                });
             if let Some(actual_velocity) = actual_velocity {
                 if actual_velocity.abs()
                     < avg_valid!(self
@@ -345,6 +374,9 @@ impl Intake {
         } else {
             self.jam_start_time = None;
             self.jam_detected = false;
+            if let Some(velocity_filter) = &mut self.velocity_filter {
+                velocity_filter.reset();
+            }
         }
 
         const SORT_DURATION: f64 = 180.0;
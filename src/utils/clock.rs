@@ -0,0 +1,62 @@
+use alloc::rc::Rc;
+use core::{cell::Cell, time::Duration};
+
+/// A source of monotonic time, measured from an arbitrary epoch.
+///
+/// Types that measure time take an `Rc<dyn Clock>` so they can be driven by a
+/// [`MockClock`] on a host or when replaying a log.
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+/// The brain's high resolution timer, with the epoch at program start.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct VexClock;
+
+impl Clock for VexClock {
+    fn now(&self) -> Duration {
+        Duration::from_micros(unsafe { vex_sdk::vexSystemHighResTimeGet() })
+    }
+}
+
+/// The default clock for types that measure time.
+pub fn vex_clock() -> Rc<dyn Clock> {
+    Rc::new(VexClock)
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+///
+/// # Example
+///
+/// ```
+/// let clock = MockClock::new();
+/// let mut tracker = DifferentialTracker::new(2).with_clock(Rc::new(clock.clone()));
+/// tracker.update(0.0);
+/// clock.advance(Duration::from_millis(10));
+/// tracker.update(1.0);
+/// assert_eq!(tracker.derivative(1), Some(0.1));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MockClock {
+    now: Rc<Cell<Duration>>,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+
+    pub fn set(&self, now: Duration) {
+        self.now.set(now);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
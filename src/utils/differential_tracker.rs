@@ -1,13 +1,34 @@
-use alloc::collections::VecDeque;
+use alloc::{collections::VecDeque, rc::Rc, vec::Vec};
+use core::time::Duration;
+use nalgebra::{DMatrix, DVector};
 use num_traits::{Float, Zero};
-use vexide::time::Instant;
+
+use super::clock::{vex_clock, Clock};
+
+/// How a [`DifferentialTracker`] estimates derivatives.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DerivativeMethod {
+    /// Repeated differences between adjacent values. The kth derivative only uses the
+    /// last k + 1 values, so it responds quickly but amplifies noise with each order.
+    FiniteDifference,
+
+    /// Fits a polynomial of this degree to the whole window by least squares against the
+    /// actual timestamps and differentiates it at the last value. With evenly spaced
+    /// timestamps, this is an end-point Savitzky–Golay filter.
+    ///
+    /// Derivatives of a higher order than the degree, or than the number of values
+    /// minus one, are unavailable.
+    LeastSquares(usize),
+}
 
 /// Tracks derivatives of a value over time.
 #[derive(Clone)]
 pub struct DifferentialTracker<T: Float> {
     n: usize,
-    data: VecDeque<(Instant, T)>,
+    data: VecDeque<(Duration, T)>,
     cumulative_integral: T,
+    clock: Rc<dyn Clock>,
+    derivative_method: DerivativeMethod,
 }
 
 impl<T: Float + Zero> DifferentialTracker<T> {
@@ -20,7 +41,31 @@ impl<T: Float + Zero> DifferentialTracker<T> {
             n,
             data: VecDeque::with_capacity(n),
             cumulative_integral: T::zero(),
+            clock: vex_clock(),
+            derivative_method: DerivativeMethod::FiniteDifference,
+        }
+    }
+
+    /// Estimates derivatives with `derivative_method` instead of finite differences.
+    pub fn with_derivative_method(mut self, derivative_method: DerivativeMethod) -> Self {
+        if let DerivativeMethod::LeastSquares(degree) = derivative_method {
+            assert!(
+                degree >= 1 && degree < self.n,
+                "The degree of the fit must be at least 1 and less than n."
+            );
         }
+        self.derivative_method = derivative_method;
+        self
+    }
+
+    /// Timestamps values with `clock`, such as a [`super::clock::MockClock`].
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn clock(&self) -> Rc<dyn Clock> {
+        self.clock.clone()
     }
 
     pub fn space_size(&self) -> usize {
@@ -35,6 +80,11 @@ impl<T: Float + Zero> DifferentialTracker<T> {
         self.cumulative_integral = T::zero();
     }
 
+    /// Overrides the accumulated integral, such as for anti-windup.
+    pub fn set_integral(&mut self, integral: T) {
+        self.cumulative_integral = integral;
+    }
+
     pub fn reset(&mut self) {
         self.data.clear();
         self.reset_integral();
@@ -43,11 +93,11 @@ impl<T: Float + Zero> DifferentialTracker<T> {
     /// Updates the tracker with a new value.
     /// If the tracker is full, it will remove the oldest value.
     pub fn update(&mut self, value: T) {
-        let now = Instant::now();
+        let now = self.clock.now();
 
         // Use trapezoidal rule to calculate the integral.
         if let Some(&(previous_time, previous_value)) = self.data.back() {
-            let dt = T::from(now.duration_since(previous_time).as_secs_f64() * 1000.0).unwrap();
+            let dt = T::from(now.saturating_sub(previous_time).as_secs_f64() * 1000.0).unwrap();
             let area = (previous_value + value) * dt / T::from(2.0).unwrap();
             self.cumulative_integral = self.cumulative_integral + area;
         }
@@ -58,6 +108,16 @@ impl<T: Float + Zero> DifferentialTracker<T> {
         self.data.push_back((now, value));
     }
 
+    /// Returns the time in milliseconds between the last two values.
+    pub fn delta_time(&self) -> Option<T> {
+        if self.data.len() < 2 {
+            return None;
+        }
+        let (t1, _) = self.data[self.data.len() - 1];
+        let (t0, _) = self.data[self.data.len() - 2];
+        T::from(t1.saturating_sub(t0).as_secs_f64() * 1000.0)
+    }
+
     pub fn at_index(&self, idx: usize) -> Option<T> {
         if idx >= self.data.len() {
             return None;
@@ -67,30 +127,70 @@ impl<T: Float + Zero> DifferentialTracker<T> {
 
     /// Returns the kth order derivative of the last value in the tracker.
     pub fn derivative(&self, k: usize) -> Option<T> {
-        if k >= self.n {
+        if k >= self.n || k >= self.data.len() {
             return None;
         }
         if k == 0 {
             return self.data.back().map(|(_, v)| *v);
         }
 
-        Some(self.compute_derivative(k, self.data.len() - 1))
+        match self.derivative_method {
+            DerivativeMethod::FiniteDifference => Some(self.finite_difference(k)),
+            DerivativeMethod::LeastSquares(degree) => self.least_squares_derivative(k, degree),
+        }
     }
 
-    fn compute_derivative(&self, k: usize, idx: usize) -> T {
-        if k == 0 {
-            return self.data[idx].1;
+    fn finite_difference(&self, k: usize) -> T {
+        let samples = self.data.range(self.data.len() - k - 1..);
+        let times = samples.clone().map(|(t, _)| *t).collect::<Vec<_>>();
+        let mut differences = samples.map(|(_, v)| *v).collect::<Vec<_>>();
+
+        // Each pass turns the differences of one order into those of the next,
+        // keeping them aligned with the later timestamp of each pair.
+        for order in 1..=k {
+            for idx in (order..=k).rev() {
+                let dt = T::from((times[idx] - times[idx - 1]).as_secs_f64() * 1000.0).unwrap();
+                if dt == T::zero() {
+                    return T::zero();
+                }
+                differences[idx] = (differences[idx] - differences[idx - 1]) / dt;
+            }
         }
+        differences[k]
+    }
 
-        let (t1, _) = self.data[idx];
-        let (t0, _) = self.data[idx - 1];
-
-        let dt = T::from(t1.duration_since(t0).as_secs_f64() * 1000.0).unwrap();
-        if dt == T::zero() {
-            return T::zero();
+    fn least_squares_derivative(&self, k: usize, degree: usize) -> Option<T> {
+        let degree = degree.min(self.data.len() - 1);
+        if k > degree {
+            return None;
         }
-
-        let dv = self.compute_derivative(k - 1, idx) - self.compute_derivative(k - 1, idx - 1);
-        dv / dt
+        let (last_time, _) = *self.data.back()?;
+
+        // Times in milliseconds relative to the last value, scaled to [-1, 0]
+        // to keep the normal equations well conditioned.
+        let times = self
+            .data
+            .iter()
+            .map(|(t, _)| -((last_time - *t).as_secs_f64() * 1000.0))
+            .collect::<Vec<_>>();
+        let scale = times.iter().fold(0.0, |max: f64, t| max.max(t.abs()));
+        if scale == 0.0 {
+            return Some(T::zero());
+        }
+        let vandermonde = DMatrix::from_fn(times.len(), degree + 1, |row, column| {
+            (times[row] / scale).powi(column as i32)
+        });
+        let values = DVector::from_iterator(
+            self.data.len(),
+            self.data.iter().map(|(_, v)| v.to_f64().unwrap_or(0.0)),
+        );
+        let coefficients = (vandermonde.transpose() * &vandermonde)
+            .cholesky()?
+            .solve(&(vandermonde.transpose() * values));
+
+        // The kth derivative of the fit at zero is k! times the kth coefficient,
+        // undoing the time scale.
+        let factorial = (1..=k).map(|i| i as f64).product::<f64>();
+        T::from(factorial * coefficients[k] / scale.powi(k as i32))
     }
-}
\ No newline at end of file
+}
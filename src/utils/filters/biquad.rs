@@ -0,0 +1,87 @@
+use num_traits::Float;
+
+use super::Filter;
+
+/// A second-order IIR filter in transposed direct form II.
+///
+/// The low-pass and notch constructors use the coefficients of the Audio EQ Cookbook.
+/// The state starts at steady state with the first sample after a reset, so there is
+/// no transient from zero.
+#[derive(Clone)]
+pub struct Biquad<T: Float> {
+    /// Feedforward coefficients `b0`, `b1` and `b2`, normalized by `a0`.
+    b: [T; 3],
+
+    /// Feedback coefficients `a1` and `a2`, normalized by `a0`.
+    a: [T; 2],
+    state: Option<[T; 2]>,
+}
+
+impl<T: Float> Biquad<T> {
+    /// Creates a filter from the coefficients of its transfer function,
+    /// `(b0 + b1 z⁻¹ + b2 z⁻²) / (a0 + a1 z⁻¹ + a2 z⁻²)`.
+    pub fn new(b: [T; 3], a: [T; 3]) -> Self {
+        assert!(a[0] != T::zero(), "a0 may not be zero.");
+        Self {
+            b: [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            a: [a[1] / a[0], a[2] / a[0]],
+            state: None,
+        }
+    }
+
+    /// Attenuates frequencies above `cutoff_frequency`.
+    /// A `q` of `1/√2` gives a Butterworth response.
+    pub fn low_pass(cutoff_frequency: T, sample_frequency: T, q: T) -> Self {
+        let (cos, alpha) = Self::intermediates(cutoff_frequency, sample_frequency, q);
+        let one = T::one();
+        let two = one + one;
+        Self::new(
+            [(one - cos) / two, one - cos, (one - cos) / two],
+            [one + alpha, -two * cos, one - alpha],
+        )
+    }
+
+    /// Rejects a narrow band around `center_frequency`, such as a vibration.
+    /// Higher values of `q` give narrower notches.
+    pub fn notch(center_frequency: T, sample_frequency: T, q: T) -> Self {
+        let (cos, alpha) = Self::intermediates(center_frequency, sample_frequency, q);
+        let one = T::one();
+        let two = one + one;
+        Self::new(
+            [one, -two * cos, one],
+            [one + alpha, -two * cos, one - alpha],
+        )
+    }
+
+    /// The cosine of the normalized angular frequency and the bandwidth term `alpha`.
+    fn intermediates(frequency: T, sample_frequency: T, q: T) -> (T, T) {
+        let two = T::one() + T::one();
+        assert!(
+            frequency > T::zero() && frequency < sample_frequency / two,
+            "Frequency must be between zero and the Nyquist frequency."
+        );
+        assert!(q > T::zero(), "Q must be positive.");
+        let omega = two * T::from(core::f64::consts::PI).unwrap() * frequency / sample_frequency;
+        (omega.cos(), omega.sin() / (two * q))
+    }
+}
+
+impl<T: Float> Filter<T> for Biquad<T> {
+    fn update(&mut self, value: T) -> T {
+        let [b0, b1, b2] = self.b;
+        let [a1, a2] = self.a;
+        let [z1, z2] = self.state.unwrap_or_else(|| {
+            let gain = (b0 + b1 + b2) / (T::one() + a1 + a2);
+            let output = gain * value;
+            let z2 = b2 * value - a2 * output;
+            [b1 * value - a1 * output + z2, z2]
+        });
+        let output = b0 * value + z1;
+        self.state = Some([b1 * value - a1 * output + z2, b2 * value - a2 * output]);
+        output
+    }
+
+    fn reset(&mut self) {
+        self.state = None;
+    }
+}
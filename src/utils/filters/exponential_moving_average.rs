@@ -0,0 +1,43 @@
+use num_traits::Float;
+
+use super::Filter;
+
+/// An exponential moving average, a first-order low-pass filter.
+///
+/// Each output moves `alpha` of the way from the previous output to the sample,
+/// so smaller values of `alpha` smooth more but lag more.
+/// The first sample after a reset is passed through.
+#[derive(Clone)]
+pub struct ExponentialMovingAverage<T: Float> {
+    alpha: T,
+    value: Option<T>,
+}
+
+impl<T: Float> ExponentialMovingAverage<T> {
+    pub fn new(alpha: T) -> Self {
+        assert!(
+            alpha > T::zero() && alpha <= T::one(),
+            "Alpha must be in (0, 1]."
+        );
+        Self { alpha, value: None }
+    }
+
+    pub fn value(&self) -> Option<T> {
+        self.value
+    }
+}
+
+impl<T: Float> Filter<T> for ExponentialMovingAverage<T> {
+    fn update(&mut self, value: T) -> T {
+        let filtered = match self.value {
+            Some(previous) => previous + self.alpha * (value - previous),
+            None => value,
+        };
+        self.value = Some(filtered);
+        filtered
+    }
+
+    fn reset(&mut self) {
+        self.value = None;
+    }
+}
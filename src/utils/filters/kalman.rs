@@ -0,0 +1,58 @@
+use num_traits::Float;
+
+use super::Filter;
+
+/// A one-dimensional Kalman filter for a value that drifts as a random walk.
+///
+/// `process_noise` is the variance the true value gains per update and
+/// `measurement_noise` the variance of each sample. Their ratio sets how much
+/// each sample is trusted.
+#[derive(Clone)]
+pub struct Kalman1D<T: Float> {
+    process_noise: T,
+    measurement_noise: T,
+
+    /// The estimate and its variance.
+    estimate: Option<(T, T)>,
+}
+
+impl<T: Float> Kalman1D<T> {
+    pub fn new(process_noise: T, measurement_noise: T) -> Self {
+        assert!(
+            process_noise >= T::zero() && measurement_noise > T::zero(),
+            "Process noise may not be negative and measurement noise must be positive."
+        );
+        Self {
+            process_noise,
+            measurement_noise,
+            estimate: None,
+        }
+    }
+
+    /// The variance of the current estimate.
+    pub fn variance(&self) -> Option<T> {
+        self.estimate.map(|(_, variance)| variance)
+    }
+}
+
+impl<T: Float> Filter<T> for Kalman1D<T> {
+    fn update(&mut self, value: T) -> T {
+        let (estimate, variance) = match self.estimate {
+            Some((estimate, variance)) => {
+                let predicted_variance = variance + self.process_noise;
+                let gain = predicted_variance / (predicted_variance + self.measurement_noise);
+                (
+                    estimate + gain * (value - estimate),
+                    (T::one() - gain) * predicted_variance,
+                )
+            }
+            None => (value, self.measurement_noise),
+        };
+        self.estimate = Some((estimate, variance));
+        estimate
+    }
+
+    fn reset(&mut self) {
+        self.estimate = None;
+    }
+}
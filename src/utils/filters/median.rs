@@ -0,0 +1,44 @@
+use alloc::{collections::VecDeque, vec::Vec};
+
+use num_traits::Float;
+
+use super::Filter;
+
+/// The median of the last `window_size` samples, which rejects outliers such as
+/// spurious distance sensor readings without smearing them into later outputs.
+///
+/// For an even number of samples, the upper of the two middle samples is used.
+/// The samples are ordered linearly, so the median is wrong for values that wrap
+/// around, such as hue near red: the median of 350, 5 and 355 is 350 rather than 355.
+#[derive(Clone)]
+pub struct MedianFilter<T: Float> {
+    window_size: usize,
+    window: VecDeque<T>,
+}
+
+impl<T: Float> MedianFilter<T> {
+    pub fn new(window_size: usize) -> Self {
+        assert!(window_size >= 1, "Window size must be at least 1.");
+        Self {
+            window_size,
+            window: VecDeque::with_capacity(window_size),
+        }
+    }
+}
+
+impl<T: Float> Filter<T> for MedianFilter<T> {
+    fn update(&mut self, value: T) -> T {
+        if self.window.len() == self.window_size {
+            self.window.pop_front();
+        }
+        self.window.push_back(value);
+
+        let mut sorted = self.window.iter().copied().collect::<Vec<_>>();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+        sorted[sorted.len() / 2]
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
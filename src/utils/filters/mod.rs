@@ -0,0 +1,15 @@
+use num_traits::Float;
+
+pub mod biquad;
+pub mod exponential_moving_average;
+pub mod kalman;
+pub mod median;
+pub mod rate_limiter;
+
+dyn_clone::clone_trait_object!(<T> Filter<T>);
+/// A filter over a stream of scalar samples.
+pub trait Filter<T: Float>: dyn_clone::DynClone {
+    /// Feeds in a new sample and returns the filtered value.
+    fn update(&mut self, value: T) -> T;
+    fn reset(&mut self);
+}
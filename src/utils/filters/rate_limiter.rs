@@ -0,0 +1,61 @@
+use alloc::rc::Rc;
+use core::time::Duration;
+
+use num_traits::Float;
+
+use super::Filter;
+use crate::utils::clock::{vex_clock, Clock};
+
+/// Limits how fast the output can rise and fall, in units per second.
+///
+/// Unlike [`crate::utils::math::delta_clamp`], it measures the time between
+/// updates itself.
+#[derive(Clone)]
+pub struct RateLimiter<T: Float> {
+    max_rise_rate: T,
+    max_fall_rate: T,
+    value: Option<T>,
+    previous_time: Option<Duration>,
+    clock: Rc<dyn Clock>,
+}
+
+impl<T: Float> RateLimiter<T> {
+    /// The rates are magnitudes. If `max_fall_rate` is `None`, it is the same as
+    /// `max_rise_rate`.
+    pub fn new(max_rise_rate: T, max_fall_rate: Option<T>) -> Self {
+        Self {
+            max_rise_rate: max_rise_rate.abs(),
+            max_fall_rate: max_fall_rate.unwrap_or(max_rise_rate).abs(),
+            value: None,
+            previous_time: None,
+            clock: vex_clock(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+impl<T: Float> Filter<T> for RateLimiter<T> {
+    fn update(&mut self, value: T) -> T {
+        let now = self.clock.now();
+        let limited = match (self.value, self.previous_time) {
+            (Some(previous), Some(previous_time)) => {
+                let dt = T::from(now.saturating_sub(previous_time).as_secs_f64()).unwrap();
+                (value - previous).clamp(-self.max_fall_rate * dt, self.max_rise_rate * dt)
+                    + previous
+            }
+            _ => value,
+        };
+        self.value = Some(limited);
+        self.previous_time = Some(now);
+        limited
+    }
+
+    fn reset(&mut self) {
+        self.value = None;
+        self.previous_time = None;
+    }
+}
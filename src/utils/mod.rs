@@ -1,3 +1,5 @@
+pub mod clock;
+pub mod filters;
 #[macro_use]
 pub mod math;
 pub mod samplers;
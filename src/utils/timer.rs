@@ -1,6 +1,7 @@
+use alloc::rc::Rc;
 use core::time::Duration;
 
-use vexide::time::Instant;
+use super::clock::{vex_clock, Clock};
 
 /// Tracks elapsed time and manages time-based delays.
 ///
@@ -14,21 +15,32 @@ use vexide::time::Instant;
 pub struct Timer {
     period: Duration,
     elapsed_duration: Duration,
-    previous_instant: Instant,
+    previous_instant: Duration,
     paused: bool,
+    clock: Rc<dyn Clock>,
 }
 
 impl Timer {
     /// Creates a new timer with the specified duration.
     pub fn new(period: Duration) -> Self {
+        let clock = vex_clock();
         Self {
             period,
             elapsed_duration: Duration::ZERO,
-            previous_instant: Instant::now(),
+            previous_instant: clock.now(),
             paused: false,
+            clock,
         }
     }
 
+    /// Measures time with `clock`, such as a [`super::clock::MockClock`]. This
+    /// resets the timer.
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self.reset();
+        self
+    }
+
     /// Returns the timer's configured period.
     pub fn period(&self) -> Duration {
         self.period
@@ -72,7 +84,7 @@ impl Timer {
     /// Resumes the timer if paused.
     pub fn resume(&mut self) {
         if self.paused {
-            self.previous_instant = Instant::now();
+            self.previous_instant = self.clock.now();
         }
         self.paused = false;
     }
@@ -80,7 +92,7 @@ impl Timer {
     /// Resets the timer to zero elapsed time.
     pub fn reset(&mut self) {
         self.elapsed_duration = Duration::ZERO;
-        self.previous_instant = Instant::now();
+        self.previous_instant = self.clock.now();
     }
 
     /// Updates the timer's period and resets it.
@@ -110,9 +122,9 @@ impl Timer {
     }
 
     fn update(&mut self) {
-        let current_instant = Instant::now();
+        let current_instant = self.clock.now();
         if !self.paused {
-            self.elapsed_duration += current_instant - self.previous_instant;
+            self.elapsed_duration += current_instant.saturating_sub(self.previous_instant);
         }
         self.previous_instant = current_instant;
     }